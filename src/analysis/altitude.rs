@@ -0,0 +1,138 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::analysis::landing::TAKEOFF_SPEED;
+use crate::parser::util::Fix;
+
+type Meters = i16;
+type Hpa = f32;
+
+const STANDARD_PRESSURE: Hpa = 1013.25;
+const METERS_PER_HPA: f32 = 8.3; //close to sea level
+
+/// The altitude reported by the analysis, the logs hold both pressure altitude and GNSS altitude
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AltitudeSource {
+    #[default]
+    Pressure, //the pressure altitude of the log, relative to 1013.25 hPa
+    Gnss,
+    Qnh, //pressure altitude corrected to the QNH estimated before takeoff
+}
+
+impl AltitudeSource {
+    pub const ALL: [AltitudeSource; 3] = [AltitudeSource::Pressure, AltitudeSource::Gnss, AltitudeSource::Qnh];
+
+    pub fn unit(&self) -> &'static str {
+        match self {
+            AltitudeSource::Pressure => "[m STD]",
+            AltitudeSource::Gnss => "[m GNSS]",
+            AltitudeSource::Qnh => "[m QNH]",
+        }
+    }
+}
+
+impl Display for AltitudeSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AltitudeSource::Pressure => "Pressure",
+            AltitudeSource::Gnss => "GNSS",
+            AltitudeSource::Qnh => "QNH",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for AltitudeSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "pressure" => Ok(AltitudeSource::Pressure),
+            "gnss" | "gps" => Ok(AltitudeSource::Gnss),
+            "qnh" => Ok(AltitudeSource::Qnh),
+            _ => Err(format!("unknown altitude {}, expected pressure, gnss or qnh", s)),
+        }
+    }
+}
+
+/// How the pressure altitudes of a log are changed to the chosen altitude
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct AltitudeCorrection {
+    pub source: AltitudeSource,
+    pub offset: Meters, //added to the pressure altitude when correcting to QNH
+}
+
+impl AltitudeCorrection {
+    pub fn new(fixes: &[Fix], source: AltitudeSource) -> Self {
+        let offset = match source {
+            AltitudeSource::Qnh => qnh_offset(fixes).unwrap_or(0),
+            AltitudeSource::Pressure | AltitudeSource::Gnss => 0,
+        };
+        Self { source, offset }
+    }
+
+    /// Replaces the pressure altitude of each fix with the chosen altitude, which every metric is then based on
+    pub fn apply(&self, fixes: &mut [Fix]) {
+        for fix in fixes {
+            match self.source {
+                AltitudeSource::Pressure => (),
+                AltitudeSource::Gnss => if let Some(alt) = fix.alt { fix.alt_igc = alt },
+                AltitudeSource::Qnh => fix.alt_igc = fix.alt_igc.saturating_add(self.offset),
+            }
+        }
+    }
+
+    /// The estimated QNH, when the altitudes are corrected to it
+    pub fn qnh(&self) -> Option<Hpa> {
+        match self.source {
+            AltitudeSource::Qnh => Some(STANDARD_PRESSURE + self.offset as f32 / METERS_PER_HPA),
+            AltitudeSource::Pressure | AltitudeSource::Gnss => None,
+        }
+    }
+}
+
+/// The median difference between GNSS and pressure altitude on the ground before takeoff,
+/// the GNSS altitude there is the elevation of the airfield.
+/// The whole log is used when the logger was switched on in the air
+fn qnh_offset(fixes: &[Fix]) -> Option<Meters> {
+    let takeoff = fixes.windows(2)
+        .position(|w| w[1].timestamp > w[0].timestamp && w[0].speed_to(&w[1]) > TAKEOFF_SPEED);
+    let ground = match takeoff {
+        Some(index) if index > 0 => &fixes[..index],
+        _ => fixes,
+    };
+    let mut offsets = ground.iter().filter_map(|fix| Some(fix.alt? - fix.alt_igc)).collect::<Vec<Meters>>();
+    offsets.sort();
+    offsets.get(offsets.len() / 2).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::util;
+    use super::*;
+
+    #[test]
+    fn altitudes_are_corrected_to_the_chosen_source() {
+        let contents = util::get_contents("examples/ast.igc").expect("failed to read file");
+        let fixes = util::get_fixes(&contents);
+        let first = fixes[0].clone();
+
+        let pressure = AltitudeCorrection::new(&fixes, AltitudeSource::Pressure);
+        let mut corrected = fixes.clone();
+        pressure.apply(&mut corrected);
+        assert_eq!(corrected[0].alt_igc, first.alt_igc);
+        assert_eq!(pressure.qnh(), None);
+
+        let gnss = AltitudeCorrection::new(&fixes, AltitudeSource::Gnss);
+        let mut corrected = fixes.clone();
+        gnss.apply(&mut corrected);
+        assert_eq!(Some(corrected[0].alt_igc), first.alt);
+
+        let qnh = AltitudeCorrection::new(&fixes, AltitudeSource::Qnh);
+        let mut corrected = fixes.clone();
+        qnh.apply(&mut corrected);
+        assert!((qnh.offset - (first.alt.expect("no GNSS altitude") - first.alt_igc)).abs() <= 2); //the logger stood still before takeoff
+        assert_eq!(corrected[0].alt_igc, first.alt_igc + qnh.offset);
+        let qnh_pressure = qnh.qnh().expect("no QNH");
+        assert!((1000. ..1040.).contains(&qnh_pressure), "QNH was {}", qnh_pressure);
+    }
+}
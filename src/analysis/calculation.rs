@@ -1,8 +1,18 @@
 use std::rc::Rc;
 use igc_parser::records::util::Time;
 
-use crate::analysis::segmenting::{Flight, Segment};
+use crate::analysis::circling::{Circling, TurnDirection};
+use crate::analysis::landing::Landing;
+use crate::analysis::segmenting::{Flight, Segment, SegmentationConfig};
+use crate::analysis::thermal::Thermal;
+use crate::analysis::glide::Glide;
+use crate::analysis::{finish, scoring, start, wind};
+use crate::analysis::util::{time_of_day, Crossing};
+use crate::analysis::wind::Wind;
+use crate::analysis::altitude::AltitudeCorrection;
+use crate::analysis::start::StartViolation;
 use crate::parser::pilot_info::PilotInfo;
+use crate::parser::security::Verification;
 use crate::parser::task::{Task, TaskComponent, TaskType};
 use crate::parser::util::Fix;
 
@@ -12,6 +22,10 @@ type Seconds = u32;
 type Kph = f32;
 type Mps = f32;
 type Percentage = f32;
+type Degrees = f32;
+type FloatSeconds = f32;
+
+const WIND_BAND: Meters = 250;
 
 
 pub struct Calculation {
@@ -22,6 +36,24 @@ pub struct Calculation {
     speed: Option<Kph>,
     distance: Option<FloatMeters>,
     qfe_alt: i16,
+    landing: Option<Landing>,
+    outcome: Outcome,
+    scored_distance: Option<FloatMeters>,
+    credited_points: Vec<(f32, f32)>,
+    leg_crossings: Vec<Option<(Crossing, Crossing)>>, //where each leg starts and ends, interpolated between the fixes
+    start_violations: Vec<StartViolation>,
+    finish_crossing: Option<Crossing>,
+    finish_below_minimum: Option<Meters>,
+    pub security: Option<Verification>, //set when the raw log is available
+    pub altitude: AltitudeCorrection, //how the altitudes of the fixes were corrected before the analysis
+}
+
+/// How the flight ended with respect to the task
+pub enum Outcome {
+    Finished { time: Seconds },
+    LandedOut { latitude: f32, longitude: f32, time: Seconds },
+    EngineUsed { latitude: f32, longitude: f32, time: Seconds },
+    NotStarted,
 }
 
 impl Calculation {
@@ -37,15 +69,64 @@ impl Calculation {
 
         let qfe_alt = fixes[0].alt_igc;
 
-        let legs = make_legs(&fixes, &task, start_time, &flight)?;
+        let start_time = start_time.or_else(|| start::detect_start(&fixes, &task));
+        let start_violations = start_time.map_or(vec![], |start_time| {
+            start::check_start(&fixes, start_time, &task.start_rules, qfe_alt, pilot_info.time_zone)
+        });
+
+        let landing = flight.landing();
+        let landing_time = landing.as_ref().map(|landing| landing.fix().timestamp);
+        let engine_time = flight.engine_runs().into_iter()
+            .map(|(engine_start, _)| engine_start)
+            .find(|engine_start| start_time.map_or(false, |start_time| *engine_start > start_time));
+        let flown_fixes = fixes.iter()
+            .filter(|fix| landing_time.map_or(true, |time| fix.timestamp <= time))
+            .filter(|fix| engine_time.map_or(true, |time| fix.timestamp <= time)) //the task ends when the engine is started
+            .map(Rc::clone)
+            .collect::<Vec<Rc<Fix>>>();
+        //the legs and the score use the same fixes and the same credited area fixes, no legs after landing or under engine
+        let area_fixes = start_time
+            .and_then(|start_time| scoring::credited_area_fixes(&flown_fixes, &task, start_time))
+            .unwrap_or_default();
+        let legs = make_legs(&flown_fixes, &task, start_time, &flight, &area_fixes)?;
+        let leg_crossings = leg_crossings(&flown_fixes, &task, &legs);
+        let (outcome, score, finish_crossing) = match start_time {
+            None => (Outcome::NotStarted, None, None),
+            Some(start_time) => {
+                let reached = scoring::reached_points(&flown_fixes, &task, start_time);
+                let outcome = match (reached.len() == task.points.len(), reached.last(), flown_fixes.last()) {
+                    (true, Some(finish_fix), _) => Outcome::Finished { time: finish_fix.timestamp },
+                    (_, _, Some(last_fix)) if engine_time.is_some() => Outcome::EngineUsed {
+                        latitude: last_fix.latitude,
+                        longitude: last_fix.longitude,
+                        time: last_fix.timestamp,
+                    },
+                    (_, _, Some(last_fix)) => Outcome::LandedOut {
+                        latitude: last_fix.latitude,
+                        longitude: last_fix.longitude,
+                        time: last_fix.timestamp,
+                    },
+                    _ => Outcome::NotStarted,
+                };
+                let finish_crossing = match (&outcome, reached.iter().rev().nth(1)) {
+                    (Outcome::Finished { .. }, Some(last_turnpoint)) => finish::finish_crossing(&flown_fixes, &task, last_turnpoint.timestamp),
+                    _ => None,
+                };
+                (outcome, scoring::score(&flown_fixes, &task, start_time, &area_fixes), finish_crossing)
+            }
+        };
+
+        let finish_below_minimum = finish_crossing.as_ref()
+            .and_then(|crossing| finish::below_minimum(crossing, &task.finish_rules, qfe_alt));
 
         let last_time = match legs.last().as_ref() {
             Some(Some(leg)) if leg.fixes.last().is_some() => {
                 leg.fixes.last().expect("unreachable").timestamp
             }
-            _ => match flight.fixes.last() {
-                Some(fix) => fix.timestamp,
-                None => panic!("No fixes in flight")
+            _ => match (flown_fixes.last(), flight.fixes.last()) {
+                (Some(fix), _) => fix.timestamp, //landed or started the engine
+                (None, Some(fix)) => fix.timestamp,
+                (None, None) => return None,
             }
         };
 
@@ -58,29 +139,40 @@ impl Calculation {
             pilot_info,
             speed,
             distance,
-            qfe_alt
+            qfe_alt,
+            landing,
+            outcome,
+            scored_distance: score.as_ref().map(|score| score.distance),
+            credited_points: score.map(|score| score.credited).unwrap_or_default(),
+            leg_crossings,
+            start_violations,
+            finish_crossing,
+            finish_below_minimum,
+            security: None,
+            altitude: AltitudeCorrection::default(),
         })
     }
 
     pub fn speed(&self, task_piece: TaskPiece) -> Option<Kph> {
         match task_piece {
             TaskPiece::EntireTask => {
-                self.speed
+                self.speed.or_else(|| self.computed_speed())
             }
             TaskPiece::Leg(leg_number) => {
                 if leg_number >= self.legs.len() {return None}
                 let leg = self.legs[leg_number].as_ref()?;
                 let points = &self.task.points;
-                let time = leg.total_time();
+                let (start, end) = self.leg_crossings.get(leg_number)?.as_ref()?;
+                let time = start.seconds_until(end);
                 match self.task.task_type {
                     TaskType::AAT(_) => {
                         let distance = leg;
                         let distance = distance.fixes.first()?.distance_to(distance.fixes.last()?);
-                        Some(3.6 * distance / (time as f32))
+                        Some(3.6 * distance / time)
                     }
                     TaskType::AST => {
                         let distance = points[leg_number].inner().distance_to(points[leg_number + 1].inner());
-                        Some(3.6 * distance / (time as f32))
+                        Some(3.6 * distance / time)
                     }
                 }
 
@@ -88,6 +180,15 @@ impl Calculation {
         }
     }
 
+    /// The speed over the scored distance for finishers, used when no speed was reported by the scoring.
+    /// The task time is measured between the interpolated start and finish crossings
+    pub fn computed_speed(&self) -> Option<Kph> {
+        if !matches!(self.outcome, Outcome::Finished { .. }) { return None };
+        let (start, _) = self.leg_crossings.first()?.as_ref()?;
+        let (_, finish) = self.leg_crossings.last()?.as_ref()?;
+        scoring::scored_speed(&self.task, self.scored_distance?, start.seconds_until(finish))
+    }
+
     pub fn glide_ratio(&self, task_piece: TaskPiece) -> Option<Kph> {
         let segments = match task_piece {
             TaskPiece::EntireTask => {
@@ -103,6 +204,7 @@ impl Calculation {
             Segment::Thermal(_) => false,
             Segment::Glide(_) => true,
             Segment::Try(_) => true,
+            Segment::Engine(_) => false,
         });
         let (dist, alt_loss) = glides.into_iter().map(|s| {
             let inner = s.inner();
@@ -125,7 +227,7 @@ impl Calculation {
     pub fn distance(&self, task_piece: TaskPiece) -> Option<FloatMeters> {
         match task_piece {
             TaskPiece::EntireTask => {
-                self.distance
+                self.distance.or(self.scored_distance)
             }
             TaskPiece::Leg(leg_number) => {
                 let leg = self.legs.get(leg_number)?;
@@ -220,29 +322,30 @@ impl Calculation {
         Some((total_alt_gain as f32) / (total_climb_time as f32))
     }
 
+    /// The time the task piece was started, interpolated to where the zone was crossed
     pub fn start_time(&self, task_piece: TaskPiece) -> Option<Time> {
-        match task_piece {
-            TaskPiece::EntireTask => {
-                if self.total_flight.fixes.is_empty() { return None };
-                let time_in_seconds = self.total_flight.fixes[0].timestamp;
-                Time::from_hms((time_in_seconds / 3600) as u8, ((time_in_seconds % 3600) / 60) as u8, (time_in_seconds % 60) as u8).ok()
-            }
-            TaskPiece::Leg(leg_number) => {
-                if self.legs.get(leg_number).is_none() || self.legs.get(leg_number)?.is_none() { return None };
-                let time_in_seconds = self.legs[leg_number].as_ref()?.fixes.first()?.timestamp;
-                Time::from_hms((time_in_seconds / 3600) as u8, ((time_in_seconds % 3600) / 60) as u8, (time_in_seconds % 60) as u8).ok()
-            }
+        let leg_number = match task_piece {
+            TaskPiece::EntireTask => 0,
+            TaskPiece::Leg(leg_number) => leg_number,
+        };
+        match (task_piece, self.leg_crossings.get(leg_number)) {
+            (_, Some(Some((start, _)))) => time_of_day(start.seconds()),
+            (TaskPiece::EntireTask, _) => time_of_day(self.total_flight.fixes.first()?.timestamp),
+            (TaskPiece::Leg(_), _) => None,
         }
     }
 
+    /// The time the task piece was ended, interpolated to where the zone was crossed
     pub fn finish_time(&self, task_piece: TaskPiece) -> Option<Time> {
-        let flight = match task_piece {
-            TaskPiece::EntireTask => Some(&self.total_flight),
-            TaskPiece::Leg(leg_number) => self.legs.get(leg_number)?.as_ref(),
+        let crossing = match task_piece {
+            TaskPiece::EntireTask => self.leg_crossings.iter().flatten().last(),
+            TaskPiece::Leg(leg_number) => self.leg_crossings.get(leg_number)?.as_ref(),
         };
-
-        let time_in_seconds = flight?.fixes.last()?.timestamp;
-        Time::from_hms((time_in_seconds / 3600) as u8, ((time_in_seconds % 3600) / 60) as u8, (time_in_seconds % 60) as u8).ok()
+        match (task_piece, crossing) {
+            (_, Some((_, end))) => time_of_day(end.seconds()),
+            (TaskPiece::EntireTask, None) => time_of_day(self.total_flight.fixes.last()?.timestamp),
+            (TaskPiece::Leg(_), None) => None,
+        }
     }
 
     pub fn start_alt(&self, task_piece: TaskPiece) -> Option<Meters> {
@@ -299,7 +402,7 @@ impl Calculation {
         };
 
         let each_glide_distance = flight?.segments.iter().filter(|seg| match seg {
-            Segment::Thermal(_) => false,
+            Segment::Thermal(_) | Segment::Engine(_) => false,
             _ => true,
         }).map(|glide| {
             let inner = glide.inner();
@@ -344,7 +447,76 @@ impl Calculation {
         Some(100. * total_alt_loss / total_alt_gain)
     }
 
-    // pub fn circling_radius(&self, task_piece: TaskPiece) -> Option<FloatMeters> { }
+    pub fn circling_radius(&self, task_piece: TaskPiece) -> Option<FloatMeters> {
+        self.average_circling(task_piece, |circling| circling.radius)
+    }
+
+    pub fn circle_time(&self, task_piece: TaskPiece) -> Option<FloatSeconds> {
+        self.average_circling(task_piece, |circling| circling.circle_time)
+    }
+
+    pub fn bank_angle(&self, task_piece: TaskPiece) -> Option<Degrees> {
+        self.average_circling(task_piece, |circling| circling.bank_angle)
+    }
+
+    pub fn left_turn_percentage(&self, task_piece: TaskPiece) -> Option<Percentage> {
+        self.average_circling(task_piece, |circling| match circling.direction {
+            TurnDirection::Left => 100.,
+            TurnDirection::Right => 0.,
+        })
+    }
+
+    pub fn thermals(&self, task_piece: TaskPiece) -> Vec<Thermal> {
+        let flight = match task_piece {
+            TaskPiece::EntireTask => Some(&self.total_flight),
+            TaskPiece::Leg(leg_number) => self.legs.get(leg_number).and_then(|leg| leg.as_ref()),
+        };
+        match flight {
+            None => vec![],
+            Some(flight) => flight.thermals().collect(),
+        }
+    }
+
+    /// The glides of the task piece compared with the task line of the leg they started in
+    pub fn glides(&self, task_piece: TaskPiece) -> Vec<Glide> {
+        let flight = match task_piece {
+            TaskPiece::EntireTask => Some(&self.total_flight),
+            TaskPiece::Leg(leg_number) => self.legs.get(leg_number).and_then(|leg| leg.as_ref()),
+        };
+        let flight = match flight {
+            None => return vec![],
+            Some(flight) => flight,
+        };
+        flight.segments.iter().filter_map(|segment| match segment {
+            Segment::Glide(fixes) => {
+                let glide = Glide::from_fixes(fixes)?;
+                let leg_number = match task_piece {
+                    TaskPiece::Leg(leg_number) => Some(leg_number),
+                    TaskPiece::EntireTask => self.legs.iter().position(|leg| match leg.as_ref().map(|leg| (leg.fixes.first(), leg.fixes.last())) {
+                        Some((Some(first), Some(last))) => (first.timestamp..=last.timestamp).contains(&glide.start_time),
+                        _ => false,
+                    }),
+                };
+                match leg_number.and_then(|leg_number| Some((self.task.points.get(leg_number)?, self.task.points.get(leg_number + 1)?))) {
+                    Some((from, to)) => Some(glide.with_leg(fixes, from.inner(), to.inner())),
+                    None => Some(glide),
+                }
+            }
+            _ => None,
+        }).collect()
+    }
+
+    /// Average over every circle flown, so thermals with many circles count more
+    fn average_circling<F: Fn(&Circling) -> f32>(&self, task_piece: TaskPiece, f: F) -> Option<f32> {
+        let flight = match task_piece {
+            TaskPiece::EntireTask => Some(&self.total_flight),
+            TaskPiece::Leg(leg_number) => self.legs.get(leg_number)?.as_ref(),
+        };
+        let circling = flight?.circling();
+        let circles = circling.iter().map(|circling| circling.circles).sum::<usize>();
+        if circles == 0 { return None };
+        Some(circling.iter().map(|circling| f(circling) * circling.circles as f32).sum::<f32>() / circles as f32)
+    }
 
     pub fn wind_thermal_gain(&self, task_piece: TaskPiece) -> Option<Percentage> {
         fn find_thermal_gain_over_leg(leg: &Flight) -> Option<Percentage> {
@@ -392,6 +564,20 @@ impl Calculation {
         }
     }
 
+    /// Average of the wind estimated in each thermal
+    pub fn wind(&self, task_piece: TaskPiece) -> Option<Wind> {
+        let flight = match task_piece {
+            TaskPiece::EntireTask => Some(&self.total_flight),
+            TaskPiece::Leg(leg_number) => self.legs.get(leg_number)?.as_ref(),
+        };
+        wind::average(&flight?.winds())
+    }
+
+    /// The wind of the task in altitude bands of `WIND_BAND` meters, from the lowest band
+    pub fn wind_profile(&self) -> Vec<Wind> {
+        wind::by_altitude_band(&self.total_flight.winds(), WIND_BAND)
+    }
+
     pub fn time_below_500m_qfe(&self, task_piece: TaskPiece) -> Option<Percentage> {
         impl Flight {
             fn time_below_500(&self, qfe_alt: Meters) -> Option<Percentage> {
@@ -411,6 +597,39 @@ impl Calculation {
         }
     }
 
+    pub fn outcome(&self) -> &Outcome {
+        &self.outcome
+    }
+
+    pub fn landing(&self) -> Option<&Landing> {
+        self.landing.as_ref()
+    }
+
+    /// The distance of the task for a finish, and the distance achieved before landing for a landout
+    pub fn scored_distance(&self) -> Option<FloatMeters> {
+        self.scored_distance
+    }
+
+    /// The latitude and longitude the task is credited through, starting with the start
+    pub fn credited_points(&self) -> &Vec<(f32, f32)> {
+        &self.credited_points
+    }
+
+    /// The start rules of the task that the start broke, empty without a start
+    pub fn start_violations(&self) -> &[StartViolation] {
+        &self.start_violations
+    }
+
+    /// The time the finish was crossed, interpolated between the fixes on either side of it
+    pub fn finish_crossing_time(&self) -> Option<FloatSeconds> {
+        self.finish_crossing.as_ref().map(|crossing| crossing.time())
+    }
+
+    /// How many meters below the minimum finish altitude the finish was, `None` when it was not below
+    pub fn finish_below_minimum(&self) -> Option<Meters> {
+        self.finish_below_minimum
+    }
+
     pub fn get_pilot_info(&self) -> &PilotInfo {
         &self.pilot_info
     }
@@ -419,6 +638,10 @@ impl Calculation {
         &self.task
     }
 
+    pub fn segmentation_config(&self) -> &SegmentationConfig {
+        &self.total_flight.config
+    }
+
     fn get_avg_speed_of_segment(&self, task_piece: TaskPiece, is_glide: bool) -> Option<Kph> {
         let flight = match task_piece {
             TaskPiece::EntireTask => {
@@ -433,6 +656,7 @@ impl Calculation {
         let flight = flight?;
         let climbs = flight.segments.iter().filter(|seg| match seg {
             Segment::Thermal(_) => !is_glide,
+            Segment::Engine(_) => false,
             _ => is_glide,
         });
         let (total_climb_dist, total_climb_time) = climbs.map(|seg| {
@@ -452,47 +676,15 @@ impl Calculation {
 
     
 }
-fn make_legs(fixes: &Vec<Rc<Fix>>, task: &Task, start_time: Option<Seconds>, flight: &Flight) -> Option<Vec<Option<Flight>>> {
-    fn make_legs_aat_aux(fixes: &Vec<Rc<Fix>>, task: &Task, start_time: Seconds, flight: &Flight, inside_turnpoints: Vec<Vec<Rc<Fix>>>, leg_times: Vec<Option<Seconds>>) -> Option<Vec<Option<Flight>>>{
-        //Getting ordered non-overlapping of consecutive sectors inside turnpoints
-        let mut inside_turnpoints = inside_turnpoints.iter().zip(leg_times.windows(2)).map(|(v, leg_time)| {
-            let start_leg = leg_time[0];
-            let end_leg = leg_time[1];
-            v.iter().filter(move |fix| match (start_leg, end_leg) {
-                (Some(start_leg), Some(end_leg)) => start_leg <= fix.timestamp && end_leg > fix.timestamp,
-                _ => false,
-            }).map(Rc::clone).collect::<Vec<Rc<Fix>>>()
-        }).collect::<Vec<Vec<Rc<Fix>>>>();
-        let finish_fix = fixes.iter().filter(|fix| match leg_times.last() {
-            Some(Some(time)) => time == &fix.timestamp,
-            _ => false,
-        }).next();
-        inside_turnpoints.push(match finish_fix {
-            None => vec![],
-            Some(fix) => vec![Rc::clone(fix)],
-        });
-        //at this point |inside_turnpoints| == |task.points|
-        let start_fixes = inside_turnpoints.remove(0);
-        if start_fixes.len() == 0 { return Some(inside_turnpoints.iter().map(|_| None).collect::<Vec<Option<Flight>>>()) }
-        inside_turnpoints.pop();
-        let mut prev_optimal = Some(Rc::clone(start_fixes.first()?));
-        assert_eq!(inside_turnpoints.len(), task.points.windows(3).count());
-        let mut leg_times = task.points.windows(3).zip(inside_turnpoints.iter()).map(|(window, fixes)|  match &prev_optimal {
-            None => None,
-            Some(prev_optimal_inner) => {
-                let (_, _, next) = (&window[0], &window[1], &window[2]);
-                let best_fix = fixes.iter()
-                    .max_by(|x, y| (x.distance_to_tp(next.inner()) + x.distance_to(prev_optimal_inner))
-                        .total_cmp(&(y.distance_to_tp(next.inner()) + y.distance_to(prev_optimal_inner))));
-
-                match best_fix {
-                    None => { prev_optimal = None; None },
-                    Some(best_fix) => { prev_optimal = Some(best_fix.clone()); Some(best_fix.timestamp) }
-                }
-            }
-        }).collect::<Vec<Option<Seconds>>>();
+fn make_legs(fixes: &Vec<Rc<Fix>>, task: &Task, start_time: Option<Seconds>, flight: &Flight, area_fixes: &[Rc<Fix>]) -> Option<Vec<Option<Flight>>> {
+    fn make_legs_aat_aux(start_time: Seconds, flight: &Flight, area_fixes: &[Rc<Fix>], leg_times: Vec<Option<Seconds>>) -> Option<Vec<Option<Flight>>>{
+        //the legs are split at the fixes credited in the areas, the same ones the distance is scored through
+        let finish_time = *leg_times.last()?;
+        let mut leg_times = (0..leg_times.len().saturating_sub(2))
+            .map(|i| area_fixes.get(i).map(|fix| fix.timestamp))
+            .collect::<Vec<Option<Seconds>>>();
         leg_times.insert(0, Some(start_time)); //add start
-        leg_times.push(finish_fix.map(|fix| fix.timestamp));
+        leg_times.push(finish_time);
         let legs = leg_times.windows(2).map(|window| {
             match (window[0], window[1]) {
                 (Some(start), Some(end)) => Some(flight.get_subflight(start, end)?),
@@ -534,13 +726,20 @@ fn make_legs(fixes: &Vec<Rc<Fix>>, task: &Task, start_time: Option<Seconds>, fli
     let mut fixes_iter = fixes.iter().filter(|fix| fix.timestamp >= start_time); //get fixes after start
     let start_fix = fixes_iter.next()?;
     let mut inside_turnpoints = turnpoints.map(|turnpoint| match turnpoint {
-        TaskComponent::Start(_) => {panic!("unexpected start token")}
+        TaskComponent::Start(_) => None, //a start after the first point is not a valid task
+        TaskComponent::Finish(finish) => { //the fixes right after finishing, not every fix inside the finish
+            Some(std::iter::once(start_fix).chain(fixes_iter.clone()).collect::<Vec<&Rc<Fix>>>()
+                .windows(2)
+                .filter(|w| finish.is_finished_between(w[0], w[1]))
+                .map(|w| Rc::clone(w[1]))
+                .collect::<Vec<Rc<Fix>>>())
+        }
         _ => {
-            fixes_iter.clone().filter(|fix| turnpoint.inner().is_inside(fix))
+            Some(fixes_iter.clone().filter(|fix| turnpoint.inner().is_inside(fix))
             .map(Rc::clone)
-            .collect::<Vec<Rc<Fix>>>()
+            .collect::<Vec<Rc<Fix>>>())
         }
-    }).collect::<Vec<Vec<Rc<Fix>>>>();
+    }).collect::<Option<Vec<Vec<Rc<Fix>>>>>()?;
     inside_turnpoints.insert(0, vec![Rc::clone(start_fix)]); //add start as the first turnpoint
 
     let mut curr_time = Some(inside_turnpoints.first()?.first()?.timestamp);
@@ -557,14 +756,33 @@ fn make_legs(fixes: &Vec<Rc<Fix>>, task: &Task, start_time: Option<Seconds>, fli
         }
     }).collect::<Vec<Option<Seconds>>>();
     leg_times.insert(0, Some(start_time));
-    inside_turnpoints.insert(0, vec![Rc::clone(start_fix)]); //add start as the first turnpoint
 
     match task.task_type {
         TaskType::AST => make_legs_ast_aux(fixes, task, flight, leg_times),
-        TaskType::AAT(_) => make_legs_aat_aux(fixes, task, start_time, flight, inside_turnpoints, leg_times),
+        TaskType::AAT(_) => make_legs_aat_aux(start_time, flight, area_fixes, leg_times),
     }
 }
 
+/// The crossings at both ends of each leg, a leg ends at the fix after its last fix where the next leg starts.
+/// Starts, turnpoints and finishes are interpolated to where the zone was crossed between that fix and the one before,
+/// the points chosen in assigned areas and the end of a landout leg are the fixes themselves
+fn leg_crossings(fixes: &[Rc<Fix>], task: &Task, legs: &[Option<Flight>]) -> Vec<Option<(Crossing, Crossing)>> {
+    let crossing_at = |point: &TaskComponent, index: usize| {
+        let fix = fixes.get(index).or(fixes.last())?; //a leg cut off by the end of the log ends at the last fix
+        let interpolated = match (&task.task_type, point) {
+            (TaskType::AAT(_), TaskComponent::Tp(_)) => None,
+            _ => index.checked_sub(1).and_then(|before| point.crossing(&fixes[before], fix)),
+        };
+        Some(interpolated.unwrap_or_else(|| Crossing::at(fix)))
+    };
+    legs.iter().enumerate().map(|(i, leg)| {
+        let leg = leg.as_ref()?;
+        let (first, last) = (leg.fixes.first()?.timestamp, leg.fixes.last()?.timestamp);
+        let start = crossing_at(&task.points[i], fixes.partition_point(|fix| fix.timestamp < first))?;
+        let end = crossing_at(task.points.get(i + 1)?, fixes.partition_point(|fix| fix.timestamp <= last))?;
+        Some((start, end))
+    }).collect()
+}
 
 #[derive(Clone, Copy)]
 pub enum TaskPiece {
@@ -0,0 +1,144 @@
+use std::rc::Rc;
+use crate::analysis::segmenting::{Flight, Segment};
+use crate::analysis::util::bearing_change;
+use crate::analysis::wind::circles;
+use crate::parser::util::Fix;
+
+type FloatMeters = f32;
+type FloatSeconds = f32;
+type Degrees = f32;
+
+const GRAVITY: f32 = 9.81;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TurnDirection {
+    Left,
+    Right,
+}
+
+/// How the glider was circling in a thermal, averaged over the complete circles
+#[derive(Debug, Clone)]
+pub struct Circling {
+    pub radius: FloatMeters,
+    pub circle_time: FloatSeconds,
+    pub bank_angle: Degrees,
+    pub direction: TurnDirection,
+    pub circles: usize,
+}
+
+impl Circling {
+    /// Fits a circle to each complete circle of the thermal after removing the drift of the circle
+    pub fn from_thermal(fixes: &[Rc<Fix>]) -> Option<Self> {
+        let fitted = circles(fixes).into_iter()
+            .filter_map(|circle| Some((fit_radius(circle)?, circle_time(circle)?)))
+            .collect::<Vec<(FloatMeters, FloatSeconds)>>();
+        if fitted.is_empty() { return None };
+
+        let count = fitted.len() as f32;
+        let radius = fitted.iter().map(|(radius, _)| radius).sum::<f32>() / count;
+        let circle_time = fitted.iter().map(|(_, time)| time).sum::<f32>() / count;
+        let air_speed = 2. * std::f32::consts::PI * radius / circle_time;
+        let bank_angle = (air_speed * air_speed / (GRAVITY * radius)).atan().to_degrees();
+
+        let turned = fixes.windows(3).map(|w| bearing_change(&w[0], &w[1], &w[2])).sum::<f32>();
+        let direction = if turned < 0. { TurnDirection::Right } else { TurnDirection::Left }; //negative is clockwise
+
+        Some(Self {
+            radius,
+            circle_time,
+            bank_angle,
+            direction,
+            circles: fitted.len(),
+        })
+    }
+}
+
+fn circle_time(circle: &[Rc<Fix>]) -> Option<FloatSeconds> {
+    let time = circle.last()?.timestamp - circle.first()?.timestamp;
+    if time == 0 { return None };
+    Some(time as f32)
+}
+
+/// Least squares circle fit of the positions relative to the air, using the drift over the circle as the wind
+fn fit_radius(circle: &[Rc<Fix>]) -> Option<FloatMeters> {
+    let first = circle.first()?;
+    let time = circle_time(circle)?;
+    let (drift_east, drift_north) = first.offset_to(circle.last()?);
+    let points = circle.iter().map(|fix| {
+        let (east, north) = first.offset_to(fix);
+        let elapsed = (fix.timestamp - first.timestamp) as f32 / time;
+        ((east - drift_east * elapsed) as f64, (north - drift_north * elapsed) as f64)
+    }).collect::<Vec<(f64, f64)>>();
+    if points.len() < 3 { return None };
+
+    //minimizes the sum of (x² + y² + Dx + Ey + F)²
+    let n = points.len() as f64;
+    let (mut sx, mut sy, mut sxx, mut syy, mut sxy, mut sxz, mut syz, mut sz) = (0., 0., 0., 0., 0., 0., 0., 0.);
+    for (x, y) in &points {
+        let z = x * x + y * y;
+        sx += x; sy += y; sxx += x * x; syy += y * y; sxy += x * y;
+        sxz += x * z; syz += y * z; sz += z;
+    }
+    let (d, e, f) = solve_3x3(
+        [[sxx, sxy, sx], [sxy, syy, sy], [sx, sy, n]],
+        [-sxz, -syz, -sz],
+    )?;
+    let radius_squared = (d * d + e * e) / 4. - f;
+    if radius_squared <= 0. { return None };
+    Some(radius_squared.sqrt() as f32)
+}
+
+/// Solves the linear system with Cramer's rule
+fn solve_3x3(m: [[f64; 3]; 3], b: [f64; 3]) -> Option<(f64, f64, f64)> {
+    let det = |m: [[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let denominator = det(m);
+    if denominator.abs() < f64::EPSILON { return None };
+    let replaced = |column: usize| {
+        let mut m = m;
+        for row in 0..3 { m[row][column] = b[row] };
+        det(m) / denominator
+    };
+    Some((replaced(0), replaced(1), replaced(2)))
+}
+
+impl Flight {
+    /// The circling of each thermal in the flight
+    pub fn circling(&self) -> Vec<Circling> {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Thermal(fixes) => Circling::from_thermal(fixes),
+            _ => None,
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radius_and_bank_angle_of_drifting_circles() {
+        let (radius, speed) = (80f32, 25f32);
+        let fixes = (0..150).map(|second| {
+            let angle = second as f32 * speed / radius; //counter-clockwise seen from above
+            let east = radius * angle.cos() + 4. * second as f32;
+            let north = radius * angle.sin();
+            Rc::new(Fix {
+                timestamp: 40_000 + second,
+                latitude: 56. + north / 111_195.,
+                longitude: 9. + east / (111_195. * 56f32.to_radians().cos()),
+                alt: Some(1000),
+                alt_igc: 1000,
+                ..Default::default()
+            })
+        }).collect::<Vec<Rc<Fix>>>();
+        let circling = Circling::from_thermal(&fixes).expect("no circles found");
+        let expected_bank = (speed * speed / (GRAVITY * radius)).atan().to_degrees();
+        assert!((circling.radius - radius).abs() < 5., "radius was {}", circling.radius);
+        assert!((circling.bank_angle - expected_bank).abs() < 3., "bank angle was {}", circling.bank_angle);
+        assert_eq!(circling.direction, TurnDirection::Left);
+    }
+}
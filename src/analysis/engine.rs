@@ -0,0 +1,130 @@
+use std::rc::Rc;
+use crate::analysis::segmenting::{Flight, Segment};
+use crate::parser::util::Fix;
+
+type Seconds = u32;
+
+const ENL_ENGINE: u16 = 500;           //noise level above this is an engine, gliding is usually below 200
+const MOP_ENGINE: u16 = 500;           //means of propulsion sensors are used for quiet engines
+const ENGINE_GAP: Seconds = 10;        //noisy fixes closer than this belong to the same engine run
+const MIN_ENGINE_TIME: Seconds = 30;   //shorter runs are ignored as noise, e.g. from a sideslip or the landing roll
+
+impl Fix {
+    pub(crate) fn is_engine_running(&self) -> bool {
+        self.enl.map_or(false, |enl| enl >= ENL_ENGINE) || self.mop.map_or(false, |mop| mop >= MOP_ENGINE)
+    }
+}
+
+impl Flight {
+    /// Finds the intervals where the engine was running, from the ENL and MOP fix extensions
+    pub fn engine_runs(&self) -> Vec<(Seconds, Seconds)> {
+        let mut runs: Vec<(Seconds, Seconds)> = vec![];
+        for fix in self.fixes.iter().filter(|fix| fix.is_engine_running()) {
+            match runs.last_mut() {
+                Some((_, end)) if fix.timestamp <= *end + ENGINE_GAP => *end = fix.timestamp,
+                _ => runs.push((fix.timestamp, fix.timestamp)),
+            }
+        }
+        runs.retain(|(start, end)| end - start >= MIN_ENGINE_TIME);
+        runs
+    }
+
+    /// Splits the segments so every fix in an engine run is in an Engine segment
+    pub(crate) fn mark_engine_runs(&mut self) {
+        let runs = self.engine_runs();
+        if runs.is_empty() { return };
+        let in_run = |fix: &Fix| runs.iter().any(|(start, end)| (*start..=*end).contains(&fix.timestamp));
+
+        let mut marked: Vec<Segment> = vec![];
+        for segment in self.segments.drain(..) {
+            let mut buildup: Vec<Rc<Fix>> = vec![];
+            let mut buildup_is_engine = false;
+            for fix in segment.inner() {
+                let is_engine = in_run(fix);
+                if is_engine != buildup_is_engine && !buildup.is_empty() {
+                    marked.push(engine_or(&segment, buildup_is_engine, buildup));
+                    buildup = vec![];
+                }
+                buildup_is_engine = is_engine;
+                buildup.push(Rc::clone(fix));
+            }
+            if !buildup.is_empty() { marked.push(engine_or(&segment, buildup_is_engine, buildup)) };
+        }
+
+        //an engine run can span several of the original segments
+        let mut segments: Vec<Segment> = vec![];
+        for segment in marked {
+            match (segments.last_mut(), segment) {
+                (Some(Segment::Engine(prev_v)), Segment::Engine(mut curr_v)) => prev_v.append(&mut curr_v),
+                (_, segment) => segments.push(segment),
+            }
+        }
+        self.segments = segments;
+    }
+}
+
+fn engine_or(segment: &Segment, is_engine: bool, inner: Vec<Rc<Fix>>) -> Segment {
+    match is_engine {
+        true => Segment::Engine(inner),
+        false => segment.with_inner(inner),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::segmenting::SegmentationConfig;
+    use super::*;
+
+    const START: Seconds = 40_000;
+
+    /// Gliding north at 1 s intervals, with the engine running and climbing in `engine` and noise levels of `noise` in `blip`
+    fn motor_glider(engine: (Seconds, Seconds), blip: (Seconds, Seconds), noise: u16) -> Vec<Fix> {
+        let mut altitude = 1500;
+        (0..=600).map(|second| {
+            let timestamp = START + second;
+            let running = (engine.0..=engine.1).contains(&second);
+            altitude += match running { true => 2, false => -1 };
+            let enl = match running || (blip.0..=blip.1).contains(&second) {
+                true => noise,
+                false => 20,
+            };
+            Fix {
+                timestamp,
+                latitude: 56. + second as f32 * 30. / 111_195.,
+                longitude: 9.,
+                alt: Some(altitude),
+                alt_igc: altitude,
+                enl: Some(enl),
+                ..Default::default()
+            }
+        }).collect()
+    }
+
+    #[test]
+    fn engine_run_is_found_and_left_out_of_the_glides() {
+        let flight = Flight::make(motor_glider((200, 259), (400, 404), 800), &SegmentationConfig::default()).expect("failed to make flight");
+        assert_eq!(flight.engine_runs(), vec![(START + 200, START + 259)]); //the short blip is noise
+
+        let engine = flight.segments.iter()
+            .filter_map(|segment| match segment {
+                Segment::Engine(fixes) => Some(fixes),
+                _ => None,
+            })
+            .collect::<Vec<&Vec<Rc<Fix>>>>();
+        assert_eq!(engine.len(), 1);
+        assert_eq!((engine[0].first().map(|fix| fix.timestamp), engine[0].last().map(|fix| fix.timestamp)), (Some(START + 200), Some(START + 259)));
+
+        let glides = flight.glides().collect::<Vec<_>>();
+        assert!(!glides.is_empty());
+        assert!(glides.iter().all(|glide| glide.end_time < START + 200 || glide.start_time > START + 259));
+        assert!(glides.iter().all(|glide| glide.end_alt < glide.start_alt)); //the climb under engine is not a glide
+        assert_eq!(flight.count_thermals(), 0);
+    }
+
+    #[test]
+    fn quiet_flight_has_no_engine_runs() {
+        let flight = Flight::make(motor_glider((200, 259), (400, 404), 150), &SegmentationConfig::default()).expect("failed to make flight");
+        assert_eq!(flight.engine_runs(), vec![]);
+        assert!(flight.segments.iter().all(|segment| !matches!(segment, Segment::Engine(_))));
+    }
+}
@@ -0,0 +1,72 @@
+use std::rc::Rc;
+use crate::analysis::util::Crossing;
+use crate::parser::task::{AltitudeReference, FinishRules, Task, TaskComponent, Turnpoint};
+use crate::parser::util::Fix;
+
+type Seconds = u32;
+type Meters = i16;
+
+impl Turnpoint {
+    /// Checks if going from one fix to the next is a finish through this zone.
+    /// A ring is finished by entering it, a line by crossing it from the course side.
+    pub(crate) fn is_finished_between(&self, from: &Fix, to: &Fix) -> bool {
+        let crossed = !self.is_inside(from) && self.is_inside(to);
+        match self.line {
+            true => crossed && from.distance_to_tp(self) <= self.r1 as f32, //entering around the end of the line does not count
+            false => crossed,
+        }
+    }
+}
+
+/// Gives the first finish through the finish zone of the task after `after`, the time the last turnpoint was reached
+pub fn finish_crossing(fixes: &[Rc<Fix>], task: &Task, after: Seconds) -> Option<Crossing> {
+    let finish = match task.points.last() {
+        Some(finish @ TaskComponent::Finish(_)) => finish,
+        _ => return None,
+    };
+    let fixes = fixes.iter().filter(|fix| fix.timestamp >= after).collect::<Vec<&Rc<Fix>>>();
+    fixes.windows(2).find_map(|w| finish.crossing(w[0], w[1]))
+}
+
+/// How far below the minimum finish altitude the finish was, if it was.
+/// AGL altitudes are measured from `ground`, the altitude of the takeoff
+pub fn below_minimum(crossing: &Crossing, rules: &FinishRules, ground: Meters) -> Option<Meters> {
+    let altitude = match rules.altitude_reference {
+        AltitudeReference::Msl => crossing.altitude,
+        AltitudeReference::Agl => crossing.altitude - ground,
+    };
+    rules.min_altitude
+        .map(|limit| limit - altitude)
+        .filter(|deficit| *deficit > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::util;
+    use super::*;
+
+    #[test]
+    fn finish_is_interpolated_and_checked_against_the_minimum() {
+        let contents = util::get_contents("examples/ast.igc").expect("failed to read file");
+        let task = Task::parse(&contents).expect("failed to parse task");
+        let fixes = util::get_fixes(&contents).into_iter().map(Rc::new).collect::<Vec<Rc<Fix>>>();
+        let finish = task.points.last().expect("no finish").inner();
+
+        let crossing = finish_crossing(&fixes, &task, fixes[0].timestamp).expect("never finished");
+        let after = fixes.iter().position(|fix| fix.timestamp == crossing.fix_time).expect("no fix after the finish");
+        let (before, after) = (&fixes[after - 1], &fixes[after]);
+        assert!(finish.is_finished_between(before, after));
+        assert!((before.timestamp as f32..=after.timestamp as f32).contains(&crossing.time()));
+        assert!((before.alt_igc.min(after.alt_igc)..=before.alt_igc.max(after.alt_igc)).contains(&crossing.altitude));
+        let distance = Fix { latitude: crossing.latitude, longitude: crossing.longitude, ..(**before).clone() }.distance_to_tp(finish);
+        assert!((distance - finish.r1 as f32).abs() < 10., "the crossing is {} m from the centre", distance);
+
+        let ground = fixes[0].alt_igc;
+        let mut rules = FinishRules { min_altitude: Some(crossing.altitude - 100), altitude_reference: AltitudeReference::Msl };
+        assert_eq!(below_minimum(&crossing, &rules, ground), None);
+        rules.min_altitude = Some(crossing.altitude + 100);
+        assert_eq!(below_minimum(&crossing, &rules, ground), Some(100));
+        rules.altitude_reference = AltitudeReference::Agl;
+        assert_eq!(below_minimum(&crossing, &rules, ground), Some(100 + ground)); //the airfield is above sea level
+    }
+}
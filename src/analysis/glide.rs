@@ -0,0 +1,147 @@
+use std::rc::Rc;
+use crate::analysis::segmenting::{Flight, Segment};
+use crate::analysis::util::{bearing_between, offset_between};
+use crate::parser::task::Turnpoint;
+use crate::parser::util::Fix;
+
+type FloatMeters = f32;
+type Meters = i16;
+type Seconds = u32;
+type Kph = f32;
+type Mps = f32;
+type Degrees = f32;
+
+const POLAR: [(Kph, Mps); 3] = [(80., 0.6), (130., 1.0), (180., 2.0)]; //sink rates of a standard class glider
+
+/// A single glide of the flight
+#[derive(Debug, Clone)]
+pub struct Glide {
+    pub start_time: Seconds,
+    pub end_time: Seconds,
+    pub start_alt: Meters,
+    pub end_alt: Meters,
+    pub time: Seconds,
+    pub distance: FloatMeters,        //straight line from start to end
+    pub flown_distance: FloatMeters,
+    pub glide_ratio: Option<f32>,     //None when no altitude was lost
+    pub avg_speed: Kph,
+    pub airspeed: Option<Kph>,        //average true airspeed when the logger records it
+    pub netto: Mps,                   //vertical air movement, assuming ground speed is air speed without airspeed
+    pub course_deviation: Option<Degrees>,
+    pub line_deviation: Option<FloatMeters>,
+}
+
+impl Glide {
+    pub fn from_fixes(fixes: &[Rc<Fix>]) -> Option<Self> {
+        let start = fixes.first()?;
+        let end = fixes.last()?;
+        let time = end.timestamp.checked_sub(start.timestamp)?;
+        if time == 0 { return None };
+        let flown_distance = fixes.windows(2).map(|w| w[0].distance_to(&w[1])).sum::<f32>();
+        let alt_loss = start.alt_igc - end.alt_igc;
+        let avg_speed = flown_distance / time as f32;
+        let vario = -alt_loss as f32 / time as f32;
+        let airspeed = fixes.iter().map(|fix| fix.tas).sum::<Option<f32>>().map(|sum| sum / fixes.len() as f32);
+        Some(Self {
+            start_time: start.timestamp,
+            end_time: end.timestamp,
+            start_alt: start.alt_igc,
+            end_alt: end.alt_igc,
+            time,
+            distance: start.distance_to(end),
+            flown_distance,
+            glide_ratio: match alt_loss > 0 {
+                true => Some(flown_distance / alt_loss as f32),
+                false => None,
+            },
+            avg_speed: avg_speed * 3.6,
+            airspeed,
+            netto: vario + polar_sink(airspeed.unwrap_or(avg_speed * 3.6)),
+            course_deviation: None,
+            line_deviation: None,
+        })
+    }
+
+    /// Compares the glide with the task line between the two turnpoints,
+    /// the course deviation is the angle between the glide and the leg
+    /// and the line deviation is the average distance of the glide fixes from the leg line
+    pub(crate) fn with_leg(mut self, fixes: &[Rc<Fix>], from: &Turnpoint, to: &Turnpoint) -> Self {
+        let from = (from.latitude, from.longitude);
+        let to = (to.latitude, to.longitude);
+        let leg_bearing = bearing_between(from, to);
+        if let (Some(start), Some(end)) = (fixes.first(), fixes.last()) {
+            let glide_bearing = start.track_to(end);
+            let deviation = (glide_bearing - leg_bearing).rem_euclid(360.);
+            self.course_deviation = Some(deviation.min(360. - deviation));
+        }
+        let (leg_east, leg_north) = offset_between(from, to);
+        let leg_length = (leg_east * leg_east + leg_north * leg_north).sqrt();
+        if leg_length > 0. && !fixes.is_empty() {
+            let cross_track = fixes.iter().map(|fix| {
+                let (east, north) = offset_between(from, (fix.latitude, fix.longitude));
+                (east * leg_north - north * leg_east).abs() / leg_length
+            }).sum::<f32>();
+            self.line_deviation = Some(cross_track / fixes.len() as f32);
+        }
+        self
+    }
+}
+
+/// Sink rate of the polar at the given speed, interpolated through the polar points
+fn polar_sink(speed: Kph) -> Mps {
+    POLAR.iter().enumerate().map(|(i, (v_i, sink_i))| {
+        let basis = POLAR.iter().enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(_, (v_j, _))| (speed - v_j) / (v_i - v_j))
+            .product::<f32>();
+        sink_i * basis
+    }).sum()
+}
+
+impl Flight {
+    /// Every glide of the flight in the order they were flown
+    pub fn glides(&self) -> impl Iterator<Item = Glide> + '_ {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Glide(fixes) => Glide::from_fixes(fixes),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::util::TurnpointRecord;
+    use super::*;
+
+    fn gliding_north(seconds: u32, speed: Mps, sink: Mps) -> Vec<Rc<Fix>> {
+        (0..=seconds).map(|second| Rc::new(Fix {
+            timestamp: 40_000 + second,
+            latitude: 56. + second as f32 * speed / 111_195.,
+            longitude: 9.,
+            alt: Some(1000),
+            alt_igc: 1000 - (sink * second as f32) as i16,
+            ..Default::default()
+        })).collect()
+    }
+
+    #[test]
+    fn glide_metrics_and_netto() {
+        let fixes = gliding_north(100, 130. / 3.6, 1.);
+        let glide = Glide::from_fixes(&fixes).expect("no glide");
+        assert!((glide.avg_speed - 130.).abs() < 1.);
+        assert!((glide.glide_ratio.expect("no glide ratio") - 36.1).abs() < 0.5);
+        assert!((glide.distance - glide.flown_distance).abs() < 1.);
+        assert!(glide.netto.abs() < 0.05); //sinking exactly as the polar
+        assert_eq!(glide.airspeed, None);
+
+        let into_wind = fixes.iter().map(|fix| Rc::new(Fix { tas: Some(180.), ..(**fix).clone() })).collect::<Vec<Rc<Fix>>>();
+        let glide_into_wind = Glide::from_fixes(&into_wind).expect("no glide");
+        assert_eq!(glide_into_wind.airspeed, Some(180.));
+        assert!((glide_into_wind.netto - 1.).abs() < 0.05); //the polar sinks 2 m/s at 180 km/h
+
+        let tp = |latitude: f32, longitude: f32| Turnpoint::parse("", TurnpointRecord { latitude, longitude, name: None }).expect("failed to parse turnpoint");
+        let glide = glide.with_leg(&fixes, &tp(56., 9.01), &tp(57., 9.01));
+        assert!(glide.course_deviation.expect("no course deviation") < 1.);
+        assert!((glide.line_deviation.expect("no line deviation") - 623.).abs() < 5.);
+    }
+}
@@ -0,0 +1,117 @@
+use std::rc::Rc;
+use crate::analysis::segmenting::Flight;
+use crate::parser::util::Fix;
+
+type Seconds = u32;
+type Meters = i16;
+type Mps = f32;
+
+pub(crate) const TAKEOFF_SPEED: Mps = 15.; //faster than this and we are flying
+const LANDED_SPEED: Mps = 4.;         //slower than this between every fix and we might be on the ground
+const LANDED_ALT_CHANGE: Meters = 15; //the altitude must not change more than this
+const LANDED_TIME: Seconds = 60;      //for this long
+const LANDED_TIME_AT_END: Seconds = 20; //or this long if the log ends before
+
+pub enum Landing {
+    /// The glider was found standing still on the ground
+    OnGround(Rc<Fix>),
+    /// The log ended before the glider was found on the ground
+    EndOfLog(Rc<Fix>),
+}
+
+impl Landing {
+    pub fn fix(&self) -> &Rc<Fix> {
+        match self {
+            Landing::OnGround(fix) => fix,
+            Landing::EndOfLog(fix) => fix,
+        }
+    }
+}
+
+impl Flight {
+    /// The first fix where the glider is moving fast enough to be flying
+    pub fn takeoff(&self) -> Option<Rc<Fix>> {
+        takeoff(&self.fixes)
+    }
+
+    /// Finds the first time after takeoff where the glider stands still with a stable altitude.
+    /// Returns `None` if the glider never took off.
+    pub fn landing(&self) -> Option<Landing> {
+        let takeoff_time = self.takeoff()?.timestamp;
+        let airborne = self.fixes.iter().position(|fix| fix.timestamp >= takeoff_time)?;
+
+        for i in airborne..self.fixes.len() {
+            let from = &self.fixes[i];
+            let window_length = self.fixes[i..].iter().position(|fix| fix.timestamp >= from.timestamp + LANDED_TIME);
+            let window = match window_length {
+                None => &self.fixes[i..], //the logger was turned off soon after
+                Some(length) => &self.fixes[i..=i + length],
+            };
+            let window_time = window.last()?.timestamp - from.timestamp;
+            if window_time < LANDED_TIME_AT_END { break }; //not enough log left to be sure
+            if is_on_ground(window) { return Some(Landing::OnGround(Rc::clone(from))) }
+        }
+
+        Some(Landing::EndOfLog(Rc::clone(self.fixes.last()?)))
+    }
+}
+
+/// The first fix where the glider is moving fast enough to be flying, the fixes after it are airborne
+pub(crate) fn takeoff(fixes: &[Rc<Fix>]) -> Option<Rc<Fix>> {
+    fixes.windows(2)
+        .find(|w| w[1].timestamp > w[0].timestamp && w[0].speed_to(&w[1]) > TAKEOFF_SPEED)
+        .map(|w| Rc::clone(&w[0]))
+}
+
+fn is_on_ground(window: &[Rc<Fix>]) -> bool {
+    let slow = window.windows(2)
+        .filter(|w| w[1].timestamp > w[0].timestamp)
+        .all(|w| w[0].speed_to(&w[1]) < LANDED_SPEED);
+    let highest = window.iter().map(|fix| fix.alt_igc).max().unwrap_or(0);
+    let lowest = window.iter().map(|fix| fix.alt_igc).min().unwrap_or(0);
+    slow && highest - lowest <= LANDED_ALT_CHANGE
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::calculation::Calculation;
+    use crate::analysis::segmenting::SegmentationConfig;
+    use crate::parser::pilot_info::PilotInfo;
+    use crate::parser::task::Task;
+    use crate::parser::util;
+    use super::*;
+
+    #[test]
+    fn outlanding_is_found_on_the_ground() {
+        let contents = util::get_contents("examples/aat_outlanding.igc").expect("failed to read file");
+        let flight = Flight::make(util::get_fixes(&contents), &SegmentationConfig::default()).expect("failed to make flight");
+        match flight.landing() {
+            Some(Landing::OnGround(fix)) => assert!(fix.timestamp < flight.fixes.last().expect("no fixes").timestamp),
+            _ => assert!(false),
+        }
+    }
+
+    #[test]
+    fn no_legs_are_flown_after_landing() {
+        let contents = util::get_contents("examples/ast.igc").expect("failed to read file");
+        let calculate = |fixes: Vec<Fix>| {
+            let task = Task::parse(&contents).expect("failed to parse task");
+            let pilot_info = PilotInfo::parse(&contents).expect("failed to parse pilot info");
+            let flight = Flight::make(fixes, &SegmentationConfig::default()).expect("failed to make flight");
+            Calculation::new(task, flight, pilot_info, None, None, None).expect("failed to calculate")
+        };
+        let original = calculate(util::get_fixes(&contents));
+        assert!(original.legs.iter().all(|leg| leg.is_some()));
+
+        //the glider stands in a field for two minutes, then the log goes on through the rest of the task
+        let landed = original.legs[1].as_ref().and_then(|leg| leg.fixes.get(60)).map(|fix| (**fix).clone()).expect("short leg");
+        let fixes = util::get_fixes(&contents).into_iter().map(|fix| match fix.timestamp {
+            time if time >= landed.timestamp && time < landed.timestamp + 120 => Fix { timestamp: time, ..landed.clone() },
+            _ => fix,
+        }).collect::<Vec<Fix>>();
+        let retrieved = calculate(fixes);
+        assert!(retrieved.legs[0].is_some() && retrieved.legs[1].is_some());
+        assert!(retrieved.legs[2..].iter().all(|leg| leg.is_none()), "legs after the landing");
+        assert!(retrieved.legs[1].as_ref().and_then(|leg| leg.fixes.last()).is_some_and(|fix| fix.timestamp <= landed.timestamp));
+    }
+}
@@ -1,3 +1,13 @@
 pub mod segmenting;
 pub mod util;
-pub mod calculation;
\ No newline at end of file
+pub mod calculation;
+pub mod start;
+pub mod finish;
+pub mod landing;
+pub mod scoring;
+pub mod engine;
+pub mod wind;
+pub mod circling;
+pub mod thermal;
+pub mod glide;
+pub mod altitude;
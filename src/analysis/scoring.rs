@@ -0,0 +1,298 @@
+use std::rc::Rc;
+use crate::analysis::util::{bearing_between, distance_between, point_at};
+use crate::parser::task::{Task, TaskComponent, TaskType, Turnpoint};
+use crate::parser::util::Fix;
+
+type FloatMeters = f32;
+type Seconds = u32;
+type FloatSeconds = f32;
+type Kph = f32;
+type Lat = f32;
+type Lon = f32;
+
+const OPTIMISING_PASSES: usize = 3; //the edge points move when their neighbours move
+const MAX_CANDIDATES: usize = 1000; //fixes considered in each area, more are thinned out evenly
+
+/// The points the task is credited through and the distance along them
+pub struct Score {
+    pub credited: Vec<(Lat, Lon)>,
+    pub distance: FloatMeters,
+}
+
+/// The points the task distance is measured through.
+/// Cylinders are touched at the edge on the shortest path, lines and sectors are measured at the centre,
+/// and so is a finish ring unless the task is reduced to its edge
+pub fn optimised_points(task: &Task) -> Vec<(Lat, Lon)> {
+    let centres = task.points.iter().map(|point| (point.inner().latitude, point.inner().longitude)).collect::<Vec<(Lat, Lon)>>();
+    let mut optimised = centres.clone();
+    for _ in 0..OPTIMISING_PASSES {
+        for (i, point) in task.points.iter().enumerate() {
+            if let TaskComponent::Finish(finish) = point {
+                if !finish.reduce { continue };
+            }
+            let point = point.inner();
+            if !point.is_cylinder() { continue };
+            let neighbours = [i.checked_sub(1).map(|j| optimised[j]), optimised.get(i + 1).copied()];
+            let (x, y) = neighbours.iter().flatten().fold((0., 0.), |(x, y), neighbour| {
+                let bearing = bearing_between(centres[i], *neighbour).to_radians();
+                (x + bearing.sin(), y + bearing.cos())
+            });
+            if x.abs() < 1e-6 && y.abs() < 1e-6 { continue }; //the leg goes straight back
+            optimised[i] = point_at(centres[i], f32::atan2(x, y).to_degrees(), point.r1 as f32);
+        }
+    }
+    optimised
+}
+
+/// Where the finish of an area task is credited coming from a point,
+/// at the edge of a finish cylinder the task is reduced to as in `optimised_points` and otherwise at the centre
+fn finish_point(finish: &Turnpoint, from: (Lat, Lon)) -> (Lat, Lon) {
+    let centre = (finish.latitude, finish.longitude);
+    match finish.reduce && finish.is_cylinder() {
+        true => point_at(centre, bearing_between(centre, from), finish.r1 as f32),
+        false => centre,
+    }
+}
+
+/// The distance of an assigned speed task, the leg distances minus the cylinder radii
+pub fn task_distance(task: &Task) -> FloatMeters {
+    optimised_points(task).windows(2).map(|w| distance_between(w[0], w[1])).sum()
+}
+
+/// The scoring speed, an assigned area task is never flown faster than over the minimum time
+pub fn scored_speed(task: &Task, distance: FloatMeters, time: FloatSeconds) -> Option<Kph> {
+    let time = match &task.task_type {
+        TaskType::AAT(min_time) => time.max(min_time.seconds_since_midnight() as f32),
+        TaskType::AST => time,
+    };
+    if time <= 0. { return None };
+    Some(3.6 * distance / time)
+}
+
+/// Chooses one fix in each area so the distance from the start through all areas to the end is the longest possible.
+/// The areas must hold the fixes of consecutive, non-overlapping parts of the flight in flight order.
+/// This is a dynamic program over the fixes of the areas, so it finds the optimum over all areas and not only the next one.
+pub fn optimise_areas(start: (Lat, Lon), areas: &[Vec<Rc<Fix>>], end: (Lat, Lon)) -> Option<Vec<Rc<Fix>>> {
+    let candidates = areas.iter().map(|area| {
+        let step = (area.len() / MAX_CANDIDATES).max(1);
+        area.iter().step_by(step).map(Rc::clone).collect::<Vec<Rc<Fix>>>()
+    }).collect::<Vec<Vec<Rc<Fix>>>>();
+    if candidates.is_empty() { return Some(vec![]) };
+    if candidates.iter().any(|area| area.is_empty()) { return None };
+    let position = |fix: &Fix| (fix.latitude, fix.longitude);
+
+    //for each candidate the longest distance from the start to it, and the candidate in the previous area it came from
+    let mut best: Vec<Vec<(FloatMeters, usize)>> = vec![];
+    for (k, area) in candidates.iter().enumerate() {
+        let layer = area.iter().map(|fix| match k {
+            0 => (distance_between(start, position(fix)), 0),
+            _ => best[k - 1].iter().zip(&candidates[k - 1])
+                .enumerate()
+                .map(|(i, ((distance, _), prev))| (distance + distance_between(position(prev), position(fix)), i))
+                .max_by(|x, y| x.0.total_cmp(&y.0))
+                .expect("unreachable"),
+        }).collect::<Vec<(FloatMeters, usize)>>();
+        best.push(layer);
+    }
+
+    let last = candidates.len() - 1;
+    let mut index = best[last].iter().zip(&candidates[last])
+        .enumerate()
+        .max_by(|(_, ((x, _), x_fix)), (_, ((y, _), y_fix))| (x + distance_between(position(x_fix), end))
+            .total_cmp(&(y + distance_between(position(y_fix), end))))?.0;
+    let mut chosen = vec![];
+    for k in (0..candidates.len()).rev() {
+        chosen.push(Rc::clone(&candidates[k][index]));
+        index = best[k][index].1;
+    }
+    chosen.reverse();
+
+    //the thinned out fixes are refined by moving one point at a time within its whole area, which never shortens the distance
+    for k in 0..chosen.len() {
+        let prev = if k == 0 { start } else { position(&chosen[k - 1]) };
+        let next = chosen.get(k + 1).map_or(end, |fix| position(fix));
+        chosen[k] = areas[k].iter()
+            .max_by(|x, y| (distance_between(prev, position(x)) + distance_between(position(x), next))
+                .total_cmp(&(distance_between(prev, position(y)) + distance_between(position(y), next))))
+            .map(Rc::clone)?;
+    }
+    Some(chosen)
+}
+
+/// Finds the first fix inside each point of the task in order, the first one being the start fix.
+/// The finish is reached by the first fix after finishing through it, see `Turnpoint::is_finished_between`.
+/// The list ends at the first point that was not reached.
+pub fn reached_points(fixes: &[Rc<Fix>], task: &Task, start_time: Seconds) -> Vec<Rc<Fix>> {
+    let fixes = fixes.iter().filter(|fix| fix.timestamp >= start_time).collect::<Vec<&Rc<Fix>>>();
+    let mut reached = match fixes.first() {
+        None => return vec![],
+        Some(start_fix) => vec![Rc::clone(start_fix)],
+    };
+    let mut index = 0;
+    for point in task.points.iter().skip(1) {
+        let found = match point {
+            TaskComponent::Finish(finish) => (index + 1..fixes.len()).find(|i| finish.is_finished_between(fixes[i - 1], fixes[*i])),
+            _ => (index + 1..fixes.len()).find(|i| point.inner().is_inside(fixes[*i])),
+        };
+        match found {
+            None => break,
+            Some(i) => {
+                reached.push(Rc::clone(fixes[i]));
+                index = i;
+            }
+        }
+    }
+    reached
+}
+
+/// The distance that counts for scoring.
+/// For a finished task it is the task distance, for a landout it is the distance of the reached points
+/// plus the best progress made towards the next point.
+/// The fixes should end where the glider landed.
+pub fn scored_distance(fixes: &[Rc<Fix>], task: &Task, start_time: Seconds) -> Option<FloatMeters> {
+    let area_fixes = credited_area_fixes(fixes, task, start_time)?;
+    score(fixes, task, start_time, &area_fixes).map(|score| score.distance)
+}
+
+/// The fix credited in each reached area of an area task, empty for an assigned speed task.
+/// The areas are optimised from the centre of the start to the centre of the finish, or of the next area for a landout
+pub fn credited_area_fixes(fixes: &[Rc<Fix>], task: &Task, start_time: Seconds) -> Option<Vec<Rc<Fix>>> {
+    if let TaskType::AST = task.task_type { return Some(vec![]) };
+    let reached = reached_points(fixes, task, start_time);
+    if reached.is_empty() { return Some(vec![]) };
+    let points = &task.points;
+    let centre = |i: usize| (points[i].inner().latitude, points[i].inner().longitude);
+    let areas = (1..reached.len().min(points.len() - 1)).map(|i| {
+        let until = reached.get(i + 1).map(|fix| fix.timestamp).unwrap_or(Seconds::MAX);
+        fixes.iter()
+            .filter(|fix| fix.timestamp >= reached[i].timestamp && fix.timestamp < until)
+            .filter(|fix| points[i].inner().is_inside(fix))
+            .map(Rc::clone)
+            .collect::<Vec<Rc<Fix>>>()
+    }).collect::<Vec<Vec<Rc<Fix>>>>();
+    let end = centre(reached.len().min(points.len() - 1)); //the finish or the next area of a landout
+    optimise_areas(centre(0), &areas, end)
+}
+
+/// Assigned speed tasks are credited through the optimised points and area tasks through `area_fixes`,
+/// the fixes of `credited_area_fixes`, for a landout that includes the progress towards the next point.
+pub fn score(fixes: &[Rc<Fix>], task: &Task, start_time: Seconds, area_fixes: &[Rc<Fix>]) -> Option<Score> {
+    let reached = reached_points(fixes, task, start_time);
+    let points = &task.points;
+    let centre = |i: usize| (points[i].inner().latitude, points[i].inner().longitude);
+    if reached.is_empty() { return None };
+    let optimised = optimised_points(task);
+    let finished = reached.len() == points.len();
+
+    let credited = match task.task_type {
+        TaskType::AST => optimised[..reached.len()].to_vec(),
+        TaskType::AAT(_) => {
+            let mut credited = vec![centre(0)];
+            credited.extend(area_fixes.iter().map(|fix| (fix.latitude, fix.longitude)));
+            if finished { credited.push(finish_point(points[points.len() - 1].inner(), *credited.last()?)) };
+            credited
+        }
+    };
+
+    let credited_distance = credited.windows(2).map(|w| distance_between(w[0], w[1])).sum::<FloatMeters>();
+    if finished { return Some(Score { credited, distance: credited_distance }) };
+
+    //landout, add the progress made towards the next point
+    let last_credited = *credited.last()?;
+    let next = match task.task_type {
+        TaskType::AST => optimised[reached.len()],
+        TaskType::AAT(_) if reached.len() == points.len() - 1 => finish_point(points[reached.len()].inner(), last_credited),
+        TaskType::AAT(_) => centre(reached.len()),
+    };
+    let leg_length = distance_between(last_credited, next);
+    let last_reached_time = reached.last()?.timestamp;
+    let progress = fixes.iter()
+        .filter(|fix| fix.timestamp >= last_reached_time)
+        .map(|fix| leg_length - distance_between((fix.latitude, fix.longitude), next))
+        .fold(0., FloatMeters::max);
+    Some(Score { credited, distance: credited_distance + progress })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::segmenting::{Flight, SegmentationConfig};
+    use crate::analysis::start;
+    use crate::parser::util;
+    use super::*;
+
+    fn scored_distance_of(path: &str) -> (usize, FloatMeters) {
+        let contents = util::get_contents(path).expect("failed to read file");
+        let task = Task::parse(&contents).expect("failed to parse task");
+        let flight = Flight::make(util::get_fixes(&contents), &SegmentationConfig::default()).expect("failed to make flight");
+        let landing_time = flight.landing().expect("no landing").fix().timestamp;
+        let fixes = flight.fixes.iter().filter(|fix| fix.timestamp <= landing_time).map(Rc::clone).collect::<Vec<Rc<Fix>>>();
+        let start_time = start::detect_start(&fixes, &task).expect("no start");
+        let reached = reached_points(&fixes, &task, start_time).len();
+        (reached, scored_distance(&fixes, &task, start_time).expect("no distance"))
+    }
+
+    #[test]
+    fn cylinder_radii_are_subtracted_from_task_distance() {
+        let contents = util::get_contents("examples/ast.igc").expect("failed to read file");
+        let task = Task::parse(&contents).expect("failed to parse task");
+        let centre_distance = task.points.windows(2).map(|w| w[0].inner().distance_to(w[1].inner())).sum::<FloatMeters>();
+        let reduction = centre_distance - task_distance(&task);
+        //the start is a line, five 500 m turnpoints are passed on both sides and the finish is a 3 km cylinder
+        assert!(reduction > 3000.);
+        assert!(reduction < 3000. + 5. * 2. * 500. + 1.);
+    }
+
+    #[test]
+    fn area_optimisation_matches_brute_force() {
+        let fix_at = |i: u32, latitude: f32, longitude: f32| Rc::new(Fix { timestamp: i, latitude, longitude, alt: Some(0), alt_igc: 0, ..Default::default() });
+        let mut seed = 7u32;
+        let mut random = || { seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12_345); (seed >> 16) as f32 / 65_536. };
+        let areas = (0..3).map(|k| (0..6).map(|i| fix_at(k * 10 + i, 56. + random() * 0.5, 9. + k as f32 * 0.5 + random() * 0.5)).collect())
+            .collect::<Vec<Vec<Rc<Fix>>>>();
+        let (start, end) = ((56., 8.5), (56.2, 10.8));
+        let length = |chosen: &[&Rc<Fix>]| {
+            let mut positions = vec![start];
+            positions.extend(chosen.iter().map(|fix| (fix.latitude, fix.longitude)));
+            positions.push(end);
+            positions.windows(2).map(|w| distance_between(w[0], w[1])).sum::<FloatMeters>()
+        };
+        let mut brute_force = 0f32;
+        for a in &areas[0] { for b in &areas[1] { for c in &areas[2] {
+            brute_force = brute_force.max(length(&[a, b, c]));
+        } } }
+        let optimised = optimise_areas(start, &areas, end).expect("no optimum");
+        assert!((length(&optimised.iter().collect::<Vec<&Rc<Fix>>>()) - brute_force).abs() < 1.);
+    }
+
+    #[test]
+    fn area_task_is_reduced_to_the_edge_of_the_finish_cylinder() {
+        let contents = util::get_contents("examples/aat.igc").expect("failed to read file");
+        let score_of = |contents: &str| {
+            let task = Task::parse(contents).expect("failed to parse task");
+            let fixes = util::get_fixes(contents).into_iter().map(Rc::new).collect::<Vec<Rc<Fix>>>();
+            let start_time = start::detect_start(&fixes, &task).expect("no start");
+            let area_fixes = credited_area_fixes(&fixes, &task, start_time).expect("no area fixes");
+            let score = score(&fixes, &task, start_time, &area_fixes).expect("no score");
+            let finish = task.points.last().expect("no finish").inner();
+            let last = *score.credited.last().expect("nothing credited");
+            (score.distance, distance_between(last, (finish.latitude, finish.longitude)), finish.r1 as f32)
+        };
+        let (reduced, to_centre, radius) = score_of(&contents);
+        assert!((to_centre - radius).abs() < 1., "credited {} m from the centre of the finish", to_centre);
+
+        let centre = contents.replace("R1=3000m,A1=180,R2=0m,A2=0,MaxAlt=0.0m,Reduce=1", "R1=3000m,A1=180,R2=0m,A2=0,MaxAlt=0.0m");
+        let (full, to_centre, _) = score_of(&centre);
+        assert!(to_centre < 1.);
+        assert!((full - reduced - radius).abs() < 1., "the finish cylinder reduced the distance by {} m", full - reduced);
+    }
+
+    #[test]
+    fn landout_scores_less_than_finish_on_same_task() {
+        let (finish_reached, finish_distance) = scored_distance_of("examples/aat.igc");
+        let (landout_reached, landout_distance) = scored_distance_of("examples/aat_outlanding.igc");
+        assert_eq!(finish_reached, 5);
+        assert!(landout_reached < 5);
+        assert!(landout_distance > 0.);
+        assert!(landout_distance < finish_distance);
+    }
+}
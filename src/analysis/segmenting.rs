@@ -1,14 +1,84 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use std::rc::Rc;
 use crate::parser::util::Fix;
 use crate::analysis;
 
+/// The thresholds used to split a flight into thermals and glides
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentationConfig {
+    pub degree_boundary: f32,   //turn this many degrees in
+    pub time_window: u32,       //this much time
+    pub connect_time: u32,      //time one has to stop thermalling for it to be a glide
+    pub thermal_backset: usize, //correcting factor for backwards looking thermal model should be roughly time_window / 2
+    pub try_time: u32,          //thermals shorter than this are only tries
+}
+
+impl Default for SegmentationConfig {
+    fn default() -> Self {
+        SegmentationPreset::Standard.config()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentationPreset {
+    Standard,
+    CoarseLogging,
+}
+
+impl SegmentationPreset {
+    pub const ALL: [SegmentationPreset; 2] = [SegmentationPreset::Standard, SegmentationPreset::CoarseLogging];
+
+    pub fn config(&self) -> SegmentationConfig {
+        match self {
+            SegmentationPreset::Standard => SegmentationConfig { // 1-2 s between fixes
+                degree_boundary: 150.,
+                time_window: 15,
+                connect_time: 35,
+                thermal_backset: 8,
+                try_time: 45,
+            },
+            SegmentationPreset::CoarseLogging => SegmentationConfig { // 4 s or more between fixes
+                degree_boundary: 180.,
+                time_window: 20,
+                connect_time: 40,
+                thermal_backset: 10,
+                try_time: 50,
+            },
+        }
+    }
+}
+
+impl Display for SegmentationPreset {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            SegmentationPreset::Standard => "Standard",
+            SegmentationPreset::CoarseLogging => "4 s+ logging",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for SegmentationPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "standard" => Ok(SegmentationPreset::Standard),
+            "coarse" => Ok(SegmentationPreset::CoarseLogging),
+            _ => Err(format!("unknown segmentation preset {}, expected standard or coarse", s)),
+        }
+    }
+}
+
 pub struct Flight {
     pub fixes: Vec<Rc<Fix>>,
-    pub segments: Vec<Segment>
+    pub segments: Vec<Segment>,
+    pub config: SegmentationConfig,
 }
 
 impl Flight {
-    pub fn make(mut fixes: Vec<Fix>) -> Option<Self> {
+    pub fn make(mut fixes: Vec<Fix>, config: &SegmentationConfig) -> Option<Self> {
         let mut segments: Vec<Segment> = vec![];
         fixes.retain(|f| f.is_valid());
         let mut prev_sound_fix = fixes.get(0)?.clone();
@@ -21,13 +91,7 @@ impl Flight {
             }});
         let fixes = fixes.into_iter().map(Rc::new).collect::<Vec<Rc<Fix>>>();
 
-        const DEGREE_BOUNDARY: f32 = 150.;  //turn this many degrees in
-        const TIME_WINDOW: u32 = 15;        //this much time
-        const CONNECT_TIME: u32 = 35;       //time one has to stop thermalling for it to be a glide
-        const THERMAL_BACKSET: usize = 8;  //correcting factor for backwards looking thermal model should be roughly TIME_WINDOW / 2
-        const TRY_TIME: u32 = 45;
-
-        let target = DEGREE_BOUNDARY / TIME_WINDOW as f32;
+        let target = config.degree_boundary / config.time_window as f32;
         let mut prev_fix = fixes.get(0)?;
         let mut curr_fix = fixes.get(0)?;
         let mut next_fix = fixes.get(0)?;
@@ -55,7 +119,7 @@ impl Flight {
                 if buildup_is_glide { //We have just started turning!
                     buildup_is_glide = false;
                     let time_of_segment = buildup.last()?.timestamp - buildup.first()?.timestamp;
-                    let segment = if time_of_segment <= CONNECT_TIME {
+                    let segment = if time_of_segment <= config.connect_time {
                         let mut prev_segment = match segments.pop() {
                             None => vec![],
                             Some(prev_segment) => prev_segment.inner().to_vec(),
@@ -91,7 +155,7 @@ impl Flight {
             }
 
             time_buildup = short_buildup.last()?.0 - short_buildup.first()?.0;
-            while time_buildup >= TIME_WINDOW {
+            while time_buildup >= config.time_window {
                 short_buildup.remove(0);
                 time_buildup = short_buildup.last()?.0 - short_buildup.first()?.0;
             }
@@ -140,9 +204,9 @@ impl Flight {
             }
         }
 
-        move_fixes_to_right_segments_by(&mut segments, THERMAL_BACKSET);
+        move_fixes_to_right_segments_by(&mut segments, config.thermal_backset);
 
-        replace_short_thermals_with_tries(&mut segments, TRY_TIME);
+        replace_short_thermals_with_tries(&mut segments, config.try_time);
 
         let segments = segments.into_iter().filter(|segment| !segment.inner().is_empty()).collect();
 
@@ -150,15 +214,17 @@ impl Flight {
         let mut flight = Self {
             fixes,
             segments,
+            config: config.clone(),
         };
         flight.combine_segments();
-        
+        flight.mark_engine_runs();
+
         Some(flight)
     }
 
     /// Combines subsequent relevant segments,
     /// after this the function will consist of Thermal, Glide, Thermal, ...
-    /// with all thermals being below TRY_TIME
+    /// with all thermals being below the configured try time
     fn combine_segments(&mut self) {
         let mut buildup = vec![];
         buildup.push(self.segments.remove(0));
@@ -179,6 +245,10 @@ impl Flight {
                             prev_v.append(&mut curr_v);
                             buildup.push(Segment::Glide(prev_v))
                         }
+                        Segment::Engine(prev_v) => {
+                            buildup.push(Segment::Engine(prev_v));
+                            buildup.push(Segment::Glide(curr_v));
+                        }
                     }
                 }
                 Segment::Thermal(mut curr_v) => {
@@ -195,6 +265,10 @@ impl Flight {
                             buildup.push(Segment::Glide(prev_v));
                             buildup.push(Segment::Thermal(curr_v));
                         }
+                        Segment::Engine(prev_v) => {
+                            buildup.push(Segment::Engine(prev_v));
+                            buildup.push(Segment::Thermal(curr_v));
+                        }
                     }
                 }
                 Segment::Try(mut curr_v) => {
@@ -211,6 +285,22 @@ impl Flight {
                             prev_v.append(&mut curr_v);
                             buildup.push(Segment::Glide(prev_v));
                         }
+                        Segment::Engine(prev_v) => {
+                            buildup.push(Segment::Engine(prev_v));
+                            buildup.push(Segment::Glide(curr_v));
+                        }
+                    }
+                }
+                Segment::Engine(mut curr_v) => {
+                    match buildup.pop().expect("unreachable") {
+                        Segment::Engine(mut prev_v) => {
+                            prev_v.append(&mut curr_v);
+                            buildup.push(Segment::Engine(prev_v));
+                        }
+                        prev => {
+                            buildup.push(prev);
+                            buildup.push(Segment::Engine(curr_v));
+                        }
                     }
                 }
             }
@@ -230,16 +320,13 @@ impl Flight {
                 && s.inner().first().expect("unreachable").timestamp < to)
             .map(|s| {
                 let inner = s.inner().clone().into_iter().filter(|fix| (from..to).contains(&fix.timestamp)).collect();
-                match s {
-                    Segment::Glide(_) => Segment::Glide(inner),
-                    Segment::Thermal(_) => Segment::Thermal(inner),
-                    Segment::Try(_) => Segment::Try(inner),
-                }
+                s.with_inner(inner)
             })
             .collect::<Vec<Segment>>();
         Some(Self {
             fixes,
             segments,
+            config: self.config.clone(),
         })
     }
 
@@ -269,6 +356,7 @@ impl Flight {
                     Segment::Glide(v) => v.len() as f32,
                     Segment::Thermal(v) => v.len() as f32,
                     Segment::Try(v) => v.len() as f32,
+                    Segment::Engine(v) => v.len() as f32,
                 }
             ).sum::<f32>();
         (thermal_length / total_length) * 100.
@@ -279,19 +367,16 @@ impl Flight {
             Segment::Glide(_) => false,
             Segment::Thermal(_) => true,
             Segment::Try(_) => false,
+            Segment::Engine(_) => false,
         }).count()
     }
-
-    pub(crate) fn total_time(&self) -> u32 {
-        if self.fixes.is_empty() { return 0 };
-        self.fixes.last().expect("unreachable").timestamp - self.fixes.first().expect("unreachable").timestamp
-    }
 }
 
 pub enum Segment {
     Glide(Vec<Rc<Fix>>),
     Thermal(Vec<Rc<Fix>>),
     Try(Vec<Rc<Fix>>),
+    Engine(Vec<Rc<Fix>>),
 }
 
 impl Segment {
@@ -300,6 +385,7 @@ impl Segment {
             Segment::Glide(v) => v,
             Segment::Thermal(v) => v,
             Segment::Try(v) => v,
+            Segment::Engine(v) => v,
         };
         if inner.is_empty() { return 0 }
         inner.last().expect("unreachable").timestamp - inner.first().expect("unreachable").timestamp
@@ -310,6 +396,7 @@ impl Segment {
             Segment::Glide(v) => v,
             Segment::Thermal(v) => v,
             Segment::Try(v) => v,
+            Segment::Engine(v) => v,
         }
     }
 
@@ -318,6 +405,37 @@ impl Segment {
             Segment::Glide(v) => v,
             Segment::Thermal(v) => v,
             Segment::Try(v) => v,
+            Segment::Engine(v) => v,
+        }
+    }
+
+    /// Makes a segment of the same kind with other fixes
+    pub(crate) fn with_inner(&self, inner: Vec<Rc<Fix>>) -> Segment {
+        match self {
+            Segment::Glide(_) => Segment::Glide(inner),
+            Segment::Thermal(_) => Segment::Thermal(inner),
+            Segment::Try(_) => Segment::Try(inner),
+            Segment::Engine(_) => Segment::Engine(inner),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::util;
+    use super::*;
+
+    #[test]
+    fn config_is_used_and_kept_on_subflights() {
+        let contents = util::get_contents("examples/ast.igc").expect("failed to read file");
+        let flight = Flight::make(util::get_fixes(&contents), &SegmentationConfig::default()).expect("failed to make flight");
+        assert!(flight.count_thermals() > 0);
+
+        let config = SegmentationConfig { try_time: u32::MAX, ..SegmentationConfig::default() };
+        let flight = Flight::make(util::get_fixes(&contents), &config).expect("failed to make flight");
+        assert_eq!(flight.count_thermals(), 0);
+        let first = flight.fixes.first().expect("no fixes").timestamp;
+        let subflight = flight.get_subflight(first, first + 3600).expect("failed to make subflight");
+        assert_eq!(subflight.config, config);
+    }
+}
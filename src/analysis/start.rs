@@ -0,0 +1,221 @@
+use std::fmt;
+use std::rc::Rc;
+use crate::analysis::landing;
+use crate::analysis::util::Offsetable;
+use crate::parser::task::{AltitudeReference, StartRules, Task, TaskComponent, Turnpoint};
+use crate::parser::util;
+use crate::parser::util::Fix;
+
+type Seconds = u32;
+type Meters = i16;
+type Kph = f32;
+
+const START_SPEED_WINDOW: Seconds = 10; //the ground speed at the start is averaged over this time before it
+
+/// A start rule that was broken, with the value at the start and the limit of the rule
+#[derive(Debug, Clone, PartialEq)]
+pub enum StartViolation {
+    BeforeGate { time: Seconds, gate: Seconds },
+    TooHigh { altitude: Meters, limit: Meters },
+    TooFast { speed: Kph, limit: Kph },
+    NotBelowPreStart { lowest: Meters, limit: Meters },
+}
+
+impl StartViolation {
+    /// How much the rule was broken by, in seconds, meters or km/h
+    pub fn excess(&self) -> f32 {
+        match self {
+            StartViolation::BeforeGate { time, gate } => gate.saturating_sub(*time) as f32,
+            StartViolation::TooHigh { altitude, limit } => (altitude - limit) as f32,
+            StartViolation::TooFast { speed, limit } => speed - limit,
+            StartViolation::NotBelowPreStart { lowest, limit } => (lowest - limit) as f32,
+        }
+    }
+}
+
+impl fmt::Display for StartViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StartViolation::BeforeGate { .. } => write!(f, "started {:.0} s before the gate opened", self.excess()),
+            StartViolation::TooHigh { limit, .. } => write!(f, "{:.0} m above the maximum start altitude of {} m", self.excess(), limit),
+            StartViolation::TooFast { limit, .. } => write!(f, "{:.0} km/h faster than the maximum start speed of {:.0} km/h", self.excess(), limit),
+            StartViolation::NotBelowPreStart { limit, .. } => write!(f, "{:.0} m above the pre-start altitude of {} m", self.excess(), limit),
+        }
+    }
+}
+
+impl Turnpoint {
+    /// Checks if going from one fix to the next is a start through this zone.
+    /// A cylinder or sector is started by leaving it, a line by crossing it.
+    pub(crate) fn is_started_between(&self, from: &Fix, to: &Fix) -> bool {
+        let crossed = self.is_inside(from) && !self.is_inside(to);
+        match self.line {
+            true => crossed && to.distance_to_tp(self) <= self.r1 as f32, //leaving around the end of the line does not count
+            false => crossed,
+        }
+    }
+}
+
+/// Gives the time of the first fix after every start through the start zone of the task
+pub fn start_crossings(fixes: &[Rc<Fix>], task: &Task) -> Vec<Seconds> {
+    let start = match task.points.first() {
+        Some(TaskComponent::Start(start)) => start,
+        _ => return vec![],
+    };
+    fixes.windows(2)
+        .filter(|w| start.is_started_between(&w[0], &w[1]))
+        .map(|w| w[1].timestamp)
+        .collect()
+}
+
+/// Finds the last valid start, which is the last start before reaching the first turnpoint.
+/// If the first turnpoint is never reached the last start of the flight is used.
+pub fn detect_start(fixes: &[Rc<Fix>], task: &Task) -> Option<Seconds> {
+    let crossings = start_crossings(fixes, task);
+    let first_crossing = *crossings.first()?;
+    let first_turnpoint = task.points.get(1)?.inner();
+    let first_turnpoint_reached = fixes.iter()
+        .filter(|fix| fix.timestamp > first_crossing)
+        .find(|fix| first_turnpoint.is_inside(fix))
+        .map(|fix| fix.timestamp);
+
+    match first_turnpoint_reached {
+        Some(reached) => crossings.into_iter().filter(|time| *time < reached).last(),
+        None => crossings.last().copied(),
+    }
+}
+
+/// Checks the start at `start_time` against the start rules.
+/// AGL altitudes are measured from `ground`, the altitude of the takeoff,
+/// and the pre-start altitude uses the same reference as the maximum start altitude, it is checked from takeoff or the gate
+pub fn check_start(fixes: &[Rc<Fix>], start_time: Seconds, rules: &StartRules, ground: Meters, utc_offset: i8) -> Vec<StartViolation> {
+    let mut violations = vec![];
+    let start_fix = match fixes.iter().find(|fix| fix.timestamp >= start_time) {
+        Some(fix) => fix,
+        None => return violations,
+    };
+
+    let gate = rules.gate_open.clone().map(|mut gate| {
+        gate.offset(-utc_offset);
+        util::unwrap_day(gate.seconds_since_midnight(), start_time)
+    });
+    if let Some(gate) = gate.filter(|gate| start_time < *gate) {
+        violations.push(StartViolation::BeforeGate { time: start_time, gate });
+    }
+
+    let altitude = |fix: &Fix| match rules.altitude_reference {
+        AltitudeReference::Msl => fix.alt_igc,
+        AltitudeReference::Agl => fix.alt_igc - ground,
+    };
+    if let Some(limit) = rules.max_altitude {
+        let altitude = altitude(start_fix);
+        if altitude > limit { violations.push(StartViolation::TooHigh { altitude, limit }) };
+    }
+
+    if let Some(limit) = rules.max_ground_speed {
+        let window = fixes.iter()
+            .filter(|fix| (start_time.saturating_sub(START_SPEED_WINDOW)..=start_fix.timestamp).contains(&fix.timestamp))
+            .collect::<Vec<&Rc<Fix>>>();
+        let time = window.last().zip(window.first()).map_or(0, |(last, first)| last.timestamp - first.timestamp);
+        if time > 0 {
+            let distance = window.windows(2).map(|w| w[0].distance_to(w[1])).sum::<f32>();
+            let speed = distance / time as f32 * 3.6;
+            if speed > limit { violations.push(StartViolation::TooFast { speed, limit }) };
+        }
+    }
+
+    if let (Some(limit), Some(takeoff)) = (rules.pre_start_altitude, landing::takeoff(fixes)) {
+        let lowest = fixes.iter()
+            .filter(|fix| fix.timestamp > takeoff.timestamp) //the glider was below every limit on the ground
+            .filter(|fix| fix.timestamp < start_time && fix.timestamp >= gate.unwrap_or(0))
+            .map(|fix| altitude(fix))
+            .min();
+        if let Some(lowest) = lowest.filter(|lowest| *lowest > limit) {
+            violations.push(StartViolation::NotBelowPreStart { lowest, limit });
+        }
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use igc_parser::records::util::Time;
+    use crate::parser::task::FinishRules;
+    use crate::parser::util::TurnpointRecord;
+    use super::*;
+
+    #[test]
+    fn start_is_detected_without_start_time() {
+        let point = |latitude: f32| TurnpointRecord { latitude, longitude: 9., name: None };
+        let descriptions = ["LSEEYOU OZ=-1,Style=2,R1=1000m,A1=180", "LSEEYOU OZ=0,Style=1,R1=500m,A1=180", "LSEEYOU OZ=1,Style=3,R1=1000m,A1=180"];
+        let task = Task::from_descriptions(
+            vec![point(56.), point(56.1), point(56.2)],
+            descriptions.iter().map(|description| description.to_string()).collect(),
+            None, StartRules::default(), FinishRules::default(),
+        ).expect("failed to make task");
+        //out of the start cylinder, back in, out again, to the first turnpoint and then out of the start once more
+        let fixes = [(100, 56.), (110, 56.02), (120, 56.), (130, 56.02), (140, 56.05), (150, 56.1), (160, 56.), (170, 56.02), (180, 56.1)]
+            .into_iter()
+            .map(|(timestamp, latitude)| Rc::new(Fix { timestamp, latitude, longitude: 9., alt: Some(1000), alt_igc: 1000, ..Default::default() }))
+            .collect::<Vec<Rc<Fix>>>();
+        assert_eq!(start_crossings(&fixes, &task), vec![110, 130, 170]);
+        assert_eq!(detect_start(&fixes, &task), Some(130)); //the last start before the first turnpoint
+        assert_eq!(detect_start(&fixes[..5], &task), Some(130)); //the last start when the turnpoint is never reached
+        assert_eq!(detect_start(&fixes[..2], &task), Some(110));
+        assert_eq!(detect_start(&fixes[..1], &task), None);
+    }
+
+    #[test]
+    fn start_is_checked_against_the_rules() {
+        let contents = util::get_contents("examples/ast.igc").expect("failed to read file");
+        let task = Task::parse(&contents).expect("failed to parse task");
+        assert!(task.start_rules.gate_open.is_some()); //NoStart of the SeeYou task options
+        let fixes = util::get_fixes(&contents).into_iter().map(Rc::new).collect::<Vec<Rc<Fix>>>();
+        let start_time = detect_start(&fixes, &task).expect("no start detected");
+        let ground = fixes[0].alt_igc;
+        assert_eq!(check_start(&fixes, start_time, &task.start_rules, ground, 2), vec![]);
+
+        let start_fix = fixes.iter().find(|fix| fix.timestamp == start_time).expect("no fix at start");
+        let strict = StartRules {
+            gate_open: Time::from_hms(23, 0, 0).ok(),
+            max_altitude: Some(start_fix.alt_igc - ground - 100),
+            altitude_reference: AltitudeReference::Agl,
+            max_ground_speed: Some(10.),
+            pre_start_altitude: None,
+        };
+        let violations = check_start(&fixes, start_time, &strict, ground, 2);
+        assert_eq!(violations.len(), 3);
+        assert!(matches!(violations[0], StartViolation::BeforeGate { gate, .. } if gate == 21 * 3600));
+        assert_eq!(violations[1], StartViolation::TooHigh { altitude: start_fix.alt_igc - ground, limit: start_fix.alt_igc - ground - 100 });
+        assert!((violations[1].excess() - 100.).abs() < f32::EPSILON);
+        assert!(matches!(violations[2], StartViolation::TooFast { .. }));
+
+        let limited = contents.replace("MaxAlt=0.0m,Line=1", "MaxAlt=100.0m,Line=1"); //the maximum altitude of the SeeYou start zone
+        let task = Task::parse(&limited).expect("failed to parse task");
+        assert_eq!(task.start_rules.max_altitude, Some(100));
+        assert!(matches!(check_start(&fixes, start_time, &task.start_rules, ground, 2)[..], [StartViolation::TooHigh { limit: 100, .. }]));
+
+        let pre_start = StartRules { gate_open: None, pre_start_altitude: Some(-1000), ..StartRules::default() };
+        assert!(matches!(check_start(&fixes, start_time, &pre_start, ground, 2)[..], [StartViolation::NotBelowPreStart { .. }]));
+    }
+
+    #[test]
+    fn pre_start_altitude_is_checked_from_takeoff() {
+        //standing on a field at 50 m, then a launch to 150 m and a climb while flying north at 30 m/s
+        let fixes = (0..=22).map(|i| {
+            let (distance, alt) = match i {
+                0..=2 => (0., 50),
+                _ => ((i - 2) as f32 * 300., 150 + (i - 3) as i16 * 50),
+            };
+            Rc::new(Fix { timestamp: i * 10, latitude: 56. + distance / 111_195., longitude: 9., alt: Some(alt), alt_igc: alt, ..Default::default() })
+        }).collect::<Vec<Rc<Fix>>>();
+
+        //the glider stays above the limit once airborne, with no gate every fix after takeoff counts
+        let rules = StartRules { pre_start_altitude: Some(100), ..StartRules::default() };
+        assert_eq!(check_start(&fixes, 200, &rules, 50, 0), vec![StartViolation::NotBelowPreStart { lowest: 150, limit: 100 }]);
+
+        let mut dipped = fixes.clone();
+        dipped[10] = Rc::new(Fix { alt_igc: 90, ..(*fixes[10]).clone() });
+        assert_eq!(check_start(&dipped, 200, &rules, 50, 0), vec![]);
+    }
+}
@@ -0,0 +1,119 @@
+use std::rc::Rc;
+use crate::analysis::segmenting::{Flight, Segment};
+use crate::parser::util::Fix;
+
+type FloatMeters = f32;
+type Meters = i16;
+type Seconds = u32;
+type Mps = f32;
+type Degrees = f32;
+type Percentage = f32;
+
+const PEAK_WINDOW: Seconds = 30; //roughly one circle
+const BEST_THERMALS: usize = 5; //how many of the day's thermals define the best climb
+
+/// A single thermal of the flight
+#[derive(Debug, Clone)]
+pub struct Thermal {
+    pub entry_time: Seconds,
+    pub exit_time: Seconds,
+    pub entry_alt: Meters,
+    pub exit_alt: Meters,
+    pub height_gain: Meters,
+    pub time: Seconds,
+    pub avg_climb: Mps,
+    pub peak_climb: Mps,
+    pub drift: FloatMeters,
+    pub drift_direction: Degrees, //the direction the thermal drifted towards
+}
+
+impl Thermal {
+    pub fn from_fixes(fixes: &[Rc<Fix>]) -> Option<Self> {
+        let entry = fixes.first()?;
+        let exit = fixes.last()?;
+        let time = exit.timestamp.checked_sub(entry.timestamp)?;
+        if time == 0 { return None };
+        let height_gain = exit.alt_igc - entry.alt_igc;
+
+        let mut window_start = fixes.iter();
+        let mut first = window_start.next()?;
+        let peak_climb = fixes.iter().filter_map(|fix| {
+            while fix.timestamp - first.timestamp > PEAK_WINDOW {
+                first = window_start.next().expect("unreachable");
+            }
+            let delta_time = fix.timestamp - first.timestamp;
+            if delta_time < PEAK_WINDOW / 2 { return None };
+            Some((fix.alt_igc - first.alt_igc) as f32 / delta_time as f32)
+        }).fold(None, |peak: Option<Mps>, climb| Some(peak.map_or(climb, |peak| peak.max(climb))));
+
+        let avg_climb = height_gain as f32 / time as f32;
+        Some(Self {
+            entry_time: entry.timestamp,
+            exit_time: exit.timestamp,
+            entry_alt: entry.alt_igc,
+            exit_alt: exit.alt_igc,
+            height_gain,
+            time,
+            avg_climb,
+            peak_climb: peak_climb.unwrap_or(avg_climb),
+            drift: entry.distance_to(exit),
+            drift_direction: entry.track_to(exit),
+        })
+    }
+
+    /// The average climb of this thermal compared to the best climb of the day
+    pub fn efficiency(&self, best_climb: Mps) -> Option<Percentage> {
+        if best_climb <= 0. { return None };
+        Some(self.avg_climb / best_climb * 100.)
+    }
+}
+
+/// The average climb of the best thermals among all the given thermals
+pub fn best_climb<'a, I: IntoIterator<Item = &'a Thermal>>(thermals: I) -> Option<Mps> {
+    let mut climbs = thermals.into_iter().map(|thermal| thermal.avg_climb).collect::<Vec<Mps>>();
+    if climbs.is_empty() { return None };
+    climbs.sort_by(|a, b| b.total_cmp(a));
+    let best = &climbs[..climbs.len().min(BEST_THERMALS)];
+    Some(best.iter().sum::<f32>() / best.len() as f32)
+}
+
+impl Flight {
+    /// Every thermal of the flight in the order they were flown
+    pub fn thermals(&self) -> impl Iterator<Item = Thermal> + '_ {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Thermal(fixes) => Thermal::from_fixes(fixes),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn climbing(seconds: u32, climb: Mps) -> Vec<Rc<Fix>> {
+        (0..=seconds).map(|second| Rc::new(Fix {
+            timestamp: 40_000 + second,
+            latitude: 56. + second as f32 * 2. / 111_195.,
+            longitude: 9.,
+            alt: Some(1000),
+            alt_igc: 1000 + (climb * second as f32) as i16,
+            ..Default::default()
+        })).collect()
+    }
+
+    #[test]
+    fn thermal_metrics() {
+        let thermal = Thermal::from_fixes(&climbing(100, 2.)).expect("no thermal");
+        assert_eq!(thermal.time, 100);
+        assert_eq!(thermal.height_gain, 200);
+        assert!((thermal.avg_climb - 2.).abs() < 0.01);
+        assert!((thermal.peak_climb - 2.).abs() < 0.1);
+        assert!((thermal.drift - 200.).abs() < 1.);
+        assert!(thermal.drift_direction.abs() < 1. || (thermal.drift_direction - 360.).abs() < 1.); //drifted north
+
+        let weak = Thermal::from_fixes(&climbing(100, 1.)).expect("no thermal");
+        let best = best_climb([&thermal, &weak]).expect("no best climb");
+        assert!((weak.efficiency(best).expect("no efficiency") - 66.7).abs() < 1.);
+    }
+}
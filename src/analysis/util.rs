@@ -1,11 +1,16 @@
 use igc_parser::records::util::Time;
 
-use crate::parser::task::Turnpoint;
-use crate::parser::util::Fix;
+use crate::parser::task::{TaskComponent, Turnpoint, ZoneOrientation};
+use crate::parser::util::{Fix, SECONDS_PER_DAY};
 
 type FloatMeters = f32;
 type Degrees = f32;
 type Mps = f32;
+type FloatSeconds = f32;
+type Seconds = u32;
+type Meters = i16;
+
+const CROSSING_BISECTIONS: usize = 12; //finds the crossing within 1/4096 of the way between the fixes
 
 impl Fix {
     pub(crate) fn distance_to(&self, fix: &Fix) -> FloatMeters {
@@ -25,6 +30,16 @@ impl Fix {
         self.distance_to(fix) / delta_time as f32
     }
 
+    /// Meters east and north to the other fix
+    pub(crate) fn offset_to(&self, fix: &Fix) -> (FloatMeters, FloatMeters) {
+        offset_between((self.latitude, self.longitude), (fix.latitude, fix.longitude))
+    }
+
+    /// The true track over the ground to the other fix
+    pub(crate) fn track_to(&self, fix: &Fix) -> Degrees {
+        bearing_between((self.latitude, self.longitude), (fix.latitude, fix.longitude))
+    }
+
     fn bearing_to(&self, fix: &Fix) -> Degrees {
         let delta_lat = fix.latitude - self.latitude ;
         let delta_lon = fix.longitude - self.longitude;
@@ -35,10 +50,49 @@ impl Fix {
 }
 
 impl Turnpoint {
+    /// Checks if the fix is inside the observation zone.
+    /// For lines the zone is the half circle behind the line, so a start is leaving it and a finish is entering it.
     pub(crate) fn is_inside(&self, fix: &Fix) -> bool {
-        let from = (self.latitude, self.longitude);
-        let to = (fix.latitude, fix.longitude);
-        distance_between(from, to) <= self.r1 as f32 //simple beer can model, should be reworked later
+        let centre = (self.latitude, self.longitude);
+        let position = (fix.latitude, fix.longitude);
+        let distance = distance_between(centre, position);
+        if distance < 1. { return self.r2 == 0 || self.a2 != 0 }; //the bearing is undefined at the centre
+
+        let off_axis = angle_between(bearing_between(centre, position), self.direction);
+        let outer_angle = if self.line { 90. } else { self.a1 as f32 };
+        let in_outer = distance <= self.r1 as f32 && off_axis <= outer_angle;
+        match (self.r2, self.a2) {
+            (0, _) => in_outer,
+            (r2, 0) => in_outer && distance >= r2 as f32, //inner radius is cut out
+            (r2, a2) => in_outer || (distance <= r2 as f32 && off_axis <= a2 as f32), //keyhole
+        }
+    }
+
+    /// Sets the direction of the zone bisector from the neighbouring points of the task
+    pub(crate) fn orient(&mut self, prev: Option<(Lat, Lon)>, next: Option<(Lat, Lon)>, start: (Lat, Lon)) {
+        let here = (self.latitude, self.longitude);
+        let away_from = |to: (Lat, Lon)| (bearing_between(here, to) + 180.) % 360.;
+        self.direction = match (self.orientation, prev, next) {
+            (ZoneOrientation::Fixed, _, _) => self.a12 as f32,
+            (ZoneOrientation::ToStart, _, _) => away_from(start),
+            (ZoneOrientation::Symmetrical, Some(prev), Some(next)) => {
+                let (to_prev, to_next) = (bearing_between(here, prev).to_radians(), bearing_between(here, next).to_radians());
+                let (x, y) = (to_prev.sin() + to_next.sin(), to_prev.cos() + to_next.cos());
+                if x.abs() < 1e-6 && y.abs() < 1e-6 { //straight through, the bisector is perpendicular to the legs
+                    (to_next.to_degrees() + 90.) % 360.
+                } else {
+                    (x.atan2(y).to_degrees() + 180.).rem_euclid(360.)
+                }
+            }
+            (ZoneOrientation::ToPrevious, Some(prev), _) | (_, Some(prev), None) => away_from(prev),
+            (_, _, Some(next)) => away_from(next),
+            (_, None, None) => self.a12 as f32,
+        };
+    }
+
+    /// A plain cylinder, which the task distance is measured to the edge of
+    pub(crate) fn is_cylinder(&self) -> bool {
+        !self.line && self.a1 >= 180 && self.r2 == 0
     }
 
     pub(crate) fn distance_to(&self, turnpoint: &Turnpoint) -> FloatMeters {
@@ -48,10 +102,85 @@ impl Turnpoint {
     }
 }
 
+/// Where and when the flight crossed the edge of a zone, interpolated between the fixes on either side of it.
+/// The time is kept as the fix after the crossing and how long before it the crossing was,
+/// so the time between two crossings is exact for any time of day
+#[derive(Debug, Clone, PartialEq)]
+pub struct Crossing {
+    pub fix_time: Seconds, //the first fix after the crossing
+    pub lead: FloatSeconds, //how long before that fix the zone was crossed
+    pub latitude: f32,
+    pub longitude: f32,
+    pub altitude: Meters,
+}
+
+impl Crossing {
+    /// A crossing at the fix itself, for a boundary that is not crossed between two fixes
+    pub(crate) fn at(fix: &Fix) -> Self {
+        Self { fix_time: fix.timestamp, lead: 0., latitude: fix.latitude, longitude: fix.longitude, altitude: fix.alt_igc }
+    }
+
+    pub fn time(&self) -> FloatSeconds {
+        self.fix_time as f32 - self.lead
+    }
+
+    /// The time rounded to whole seconds
+    pub fn seconds(&self) -> Seconds {
+        self.fix_time - self.lead.round() as Seconds
+    }
+
+    /// The seconds from this crossing to a later one
+    pub fn seconds_until(&self, later: &Crossing) -> FloatSeconds {
+        later.fix_time.saturating_sub(self.fix_time) as f32 - later.lead + self.lead
+    }
+}
+
+impl TaskComponent {
+    /// The crossing of the zone between two fixes in the direction that counts for the task,
+    /// which is leaving a start, entering a turnpoint and finishing through a finish
+    pub(crate) fn crossing(&self, from: &Fix, to: &Fix) -> Option<Crossing> {
+        let (crossed, leaving, zone) = match self {
+            TaskComponent::Start(start) => (start.is_started_between(from, to), true, start),
+            TaskComponent::Tp(tp) => (!tp.is_inside(from) && tp.is_inside(to), false, tp),
+            TaskComponent::Finish(finish) => (finish.is_finished_between(from, to), false, finish),
+        };
+        match crossed {
+            true => Some(crossing_between(from, to, |fix| zone.is_inside(fix) != leaving)),
+            false => None,
+        }
+    }
+}
+
+/// Finds the crossing on the straight line between two fixes, `is_crossed` is false for `from` and true for `to`
+pub(crate) fn crossing_between(from: &Fix, to: &Fix, is_crossed: impl Fn(&Fix) -> bool) -> Crossing {
+    let at = |fraction: f32| {
+        let mut fix = from.clone();
+        fix.latitude += (to.latitude - from.latitude) * fraction;
+        fix.longitude += (to.longitude - from.longitude) * fraction;
+        fix
+    };
+    let (mut before, mut after) = (0., 1.);
+    for _ in 0..CROSSING_BISECTIONS {
+        let middle = (before + after) / 2.;
+        match is_crossed(&at(middle)) {
+            true => after = middle,
+            false => before = middle,
+        }
+    }
+    let crossed = at(after);
+    Crossing {
+        fix_time: to.timestamp,
+        lead: to.timestamp.saturating_sub(from.timestamp) as f32 * (1. - after),
+        latitude: crossed.latitude,
+        longitude: crossed.longitude,
+        altitude: (from.alt_igc as f32 + (to.alt_igc - from.alt_igc) as f32 * after).round() as Meters,
+    }
+}
+
 type Lat = f32;
 type Lon = f32;
 
-fn distance_between(from: (Lat, Lon), to: (Lat, Lon)) -> FloatMeters {
+pub(crate) fn distance_between(from: (Lat, Lon), to: (Lat, Lon)) -> FloatMeters {
     let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
     let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
 
@@ -61,6 +190,42 @@ fn distance_between(from: (Lat, Lon), to: (Lat, Lon)) -> FloatMeters {
     (y*y + x*x).sqrt() * 6_371_000.
 }
 
+/// Meters east and north from one position to another
+pub(crate) fn offset_between(from: (Lat, Lon), to: (Lat, Lon)) -> (FloatMeters, FloatMeters) {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+
+    let x = (lon2 - lon1) * ((lat1 + lat2) / 2.).cos();
+    let y = lat2 - lat1;
+
+    (x * 6_371_000., y * 6_371_000.)
+}
+
+/// The position at the distance and bearing from another position, accurate for the size of observation zones
+pub(crate) fn point_at(from: (Lat, Lon), bearing: Degrees, distance: FloatMeters) -> (Lat, Lon) {
+    let (east, north) = (distance * bearing.to_radians().sin(), distance * bearing.to_radians().cos());
+    let lat = from.0 + (north / 6_371_000.).to_degrees();
+    let lon = from.1 + (east / (6_371_000. * ((from.0 + lat) / 2.).to_radians().cos())).to_degrees();
+    (lat, lon)
+}
+
+/// Bearing from one position to another with 0 being north and 90 east
+pub(crate) fn bearing_between(from: (Lat, Lon), to: (Lat, Lon)) -> Degrees {
+    let (lat1, lon1) = (from.0.to_radians(), from.1.to_radians());
+    let (lat2, lon2) = (to.0.to_radians(), to.1.to_radians());
+
+    let x = (lon2 - lon1) * ((lat1 + lat2) / 2.).cos();
+    let y = lat2 - lat1;
+
+    x.atan2(y).to_degrees().rem_euclid(360.)
+}
+
+/// The smallest angle between two bearings, always in the range 0 to 180
+fn angle_between(first: Degrees, second: Degrees) -> Degrees {
+    let delta = (first - second).rem_euclid(360.);
+    delta.min(360. - delta)
+}
+
 /// Negative is clockwise.
 /// Positive is counter-clockwise.
 pub fn bearing_change(first: &Fix, second: &Fix, last: &Fix) -> Degrees {
@@ -91,7 +256,77 @@ pub trait Offsetable {
 }
 
 impl Offsetable for Time {
+    /// Moves the time by whole hours, wrapping around midnight
     fn offset(&mut self, offset: i8) {
-        self.add_hours(offset as _);
+        let seconds = self.seconds_since_midnight() as i64 + offset as i64 * 3600;
+        if let Some(time) = time_of_day(seconds.rem_euclid(SECONDS_PER_DAY as i64) as u32) { *self = time };
+    }
+}
+
+/// The time of day of a fix timestamp, which may have counted past midnight
+pub fn time_of_day(seconds: u32) -> Option<Time> {
+    let seconds = seconds % SECONDS_PER_DAY;
+    Time::from_hms((seconds / 3600) as u8, ((seconds % 3600) / 60) as u8, (seconds % 60) as u8).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::util::TurnpointRecord;
+    use super::*;
+
+    fn fix_at(latitude: f32, longitude: f32) -> Fix {
+        Fix { timestamp: 0, latitude, longitude, alt: Some(0), alt_igc: 0, ..Default::default() }
+    }
+
+    fn turnpoint(description: &str) -> Turnpoint {
+        Turnpoint::parse(description, TurnpointRecord { latitude: 56., longitude: 9., name: None }).expect("failed to parse turnpoint")
+    }
+
+    #[test]
+    fn fai_sector_points_away_from_the_course() {
+        let mut tp = turnpoint("LSEEYOU OZ=1,Style=1,R1=10000m,A1=45,R2=0m,A2=0");
+        tp.orient(Some((55.9, 9.)), Some((56., 9.2)), (55.9, 9.)); //course comes from south and continues east
+        assert!(tp.is_inside(&fix_at(56.03, 8.97)));  //north west of the point
+        assert!(!tp.is_inside(&fix_at(55.98, 9.02))); //on the course side of the point
+        assert!(!tp.is_inside(&fix_at(56.03, 9.02))); //north east is outside the 90 degree sector
+    }
+
+    #[test]
+    fn keyhole_contains_inner_cylinder() {
+        let mut tp = turnpoint("LSEEYOU OZ=1,Style=1,R1=10000m,A1=45,R2=500m,A2=180");
+        tp.orient(Some((55.9, 9.)), Some((56., 9.2)), (55.9, 9.));
+        assert!(tp.is_inside(&fix_at(55.997, 9.002)));
+        assert!(!tp.is_inside(&fix_at(55.99, 9.01)));
+    }
+
+    #[test]
+    fn start_line_is_left_towards_the_first_leg() {
+        let mut tp = turnpoint("LSEEYOU OZ=-1,Style=2,R1=5000m,A1=180,R2=0m,A2=0,Line=1");
+        tp.orient(None, Some((56.2, 9.)), (56., 9.));
+        assert!(tp.is_inside(&fix_at(55.98, 9.01)));
+        assert!(!tp.is_inside(&fix_at(56.02, 9.01)));
+        assert!(!tp.is_inside(&fix_at(55.9, 9.))); //too far behind the line
+    }
+
+    #[test]
+    fn crossings_are_interpolated_between_fixes() {
+        let mut line = turnpoint("LSEEYOU OZ=-1,Style=2,R1=5000m,A1=180,R2=0m,A2=0,Line=1");
+        line.orient(None, Some((56.2, 9.)), (56., 9.));
+        let start = TaskComponent::Start(line);
+        let before = Fix { timestamp: 100, alt_igc: 1000, ..fix_at(55.99, 9.) };
+        let after = Fix { timestamp: 110, alt_igc: 900, ..fix_at(56.01, 9.) };
+        let crossing = start.crossing(&before, &after).expect("the line was not crossed");
+        assert_eq!(crossing.fix_time, 110);
+        assert!((crossing.time() - 105.).abs() < 0.01, "crossed at {}", crossing.time());
+        assert_eq!((crossing.seconds(), crossing.altitude), (105, 950));
+        assert!((crossing.latitude - 56.).abs() < 1e-4);
+        assert_eq!(start.crossing(&after, &before), None); //crossing back is not a start
+
+        let cylinder = TaskComponent::Tp(turnpoint("LSEEYOU OZ=1,Style=1,R1=1000m,A1=180,R2=0m,A2=0"));
+        let outside = Fix { timestamp: 200, ..fix_at(55.982, 9.) };
+        let inside = Fix { timestamp: 204, ..fix_at(55.9982, 9.) };
+        let entry = cylinder.crossing(&outside, &inside).expect("the cylinder was not entered");
+        assert!((crossing.seconds_until(&entry) - 97.2).abs() < 0.1, "{} s between the crossings", crossing.seconds_until(&entry));
+        assert_eq!(cylinder.crossing(&inside, &outside), None);
     }
 }
@@ -0,0 +1,226 @@
+use std::rc::Rc;
+use crate::analysis::segmenting::{Flight, Segment};
+use crate::analysis::util::bearing_change;
+use crate::parser::util::Fix;
+
+type Mps = f32;
+type Degrees = f32;
+type Meters = i16;
+type Seconds = u32;
+
+#[derive(Debug, Clone)]
+pub struct Wind {
+    pub speed: Mps,
+    pub direction: Degrees, //where the wind is coming from
+    pub altitude: Meters,
+    pub time: Seconds,
+}
+
+impl Wind {
+    fn from_velocity(east: Mps, north: Mps, altitude: Meters, time: Seconds) -> Self {
+        Self {
+            speed: (east * east + north * north).sqrt(),
+            direction: (-east).atan2(-north).to_degrees().rem_euclid(360.),
+            altitude,
+            time,
+        }
+    }
+
+    /// The velocity of the air mass east and north
+    fn velocity(&self) -> (Mps, Mps) {
+        let towards = (self.direction + 180.).to_radians();
+        (self.speed * towards.sin(), self.speed * towards.cos())
+    }
+
+    /// Estimates the wind in a thermal from the drift of each complete circle
+    /// and from the difference between ground speed and true airspeed in each circle,
+    /// or between the highest and lowest ground speed when the logger has no airspeed
+    pub fn from_thermal(fixes: &[Rc<Fix>]) -> Option<Self> {
+        let circles = circles(fixes);
+        if circles.is_empty() { return None };
+        let altitude = (fixes.iter().map(|fix| fix.alt_igc as i32).sum::<i32>() / fixes.len() as i32) as Meters;
+        let time = (fixes.first()?.timestamp + fixes.last()?.timestamp) / 2;
+
+        let (drift_east, drift_north, drift_time) = circles.iter().map(|circle| {
+            let (first, last) = (circle.first().expect("unreachable"), circle.last().expect("unreachable"));
+            let (east, north) = first.offset_to(last);
+            (east, north, last.timestamp - first.timestamp)
+        }).fold((0., 0., 0), |acc, (east, north, time)| (acc.0 + east, acc.1 + north, acc.2 + time));
+        if drift_time == 0 { return None };
+        let drift = Wind::from_velocity(drift_east / drift_time as f32, drift_north / drift_time as f32, altitude, time);
+
+        let ground_speed_winds = circles.iter().filter_map(|circle| {
+            if let Some(wind) = Wind::from_airspeed(circle, altitude, time) { return Some(wind) };
+            let speeds = circle.windows(2)
+                .filter(|w| w[1].timestamp > w[0].timestamp)
+                .map(|w| (w[0].speed_to(&w[1]), w[0].track_to(&w[1])))
+                .collect::<Vec<(Mps, Degrees)>>();
+            let (fastest, fastest_track) = *speeds.iter().max_by(|x, y| x.0.total_cmp(&y.0))?;
+            let (slowest, _) = *speeds.iter().min_by(|x, y| x.0.total_cmp(&y.0))?;
+            Some(Wind {
+                speed: (fastest - slowest) / 2.,
+                direction: (fastest_track + 180.) % 360., //fastest when flying downwind
+                altitude,
+                time,
+            })
+        }).collect::<Vec<Wind>>();
+
+        match average(&ground_speed_winds) {
+            None => Some(drift),
+            Some(ground_speed) => average(&[drift, ground_speed]),
+        }
+    }
+
+    /// The wind is the difference between the ground velocity and the air velocity.
+    /// With a logged true heading this is exact, otherwise the wind is fitted to the difference between
+    /// ground speed and airspeed along the track, which works when the circle covers every direction
+    fn from_airspeed(circle: &[Rc<Fix>], altitude: Meters, time: Seconds) -> Option<Self> {
+        let samples = circle.windows(2)
+            .filter(|w| w[1].timestamp > w[0].timestamp)
+            .map(|w| {
+                let airspeed = (w[0].tas? + w[1].tas?) / 2. / 3.6;
+                let seconds = (w[1].timestamp - w[0].timestamp) as f32;
+                let (east, north) = w[0].offset_to(&w[1]);
+                Some((east / seconds, north / seconds, airspeed, w[1].heading))
+            })
+            .collect::<Option<Vec<(Mps, Mps, Mps, Option<u16>)>>>()?;
+        if samples.is_empty() { return None };
+        let count = samples.len() as f32;
+
+        if samples.iter().all(|(_, _, _, heading)| heading.is_some()) {
+            let (east, north) = samples.iter().map(|(east, north, airspeed, heading)| {
+                let heading = (heading.expect("unreachable") as f32).to_radians();
+                (east - airspeed * heading.sin(), north - airspeed * heading.cos())
+            }).fold((0., 0.), |acc, (east, north)| (acc.0 + east, acc.1 + north));
+            return Some(Wind::from_velocity(east / count, north / count, altitude, time));
+        }
+
+        //least squares of (ground speed - airspeed) = wind . track direction
+        let (mut ee, mut en, mut nn, mut de, mut dn) = (0., 0., 0., 0., 0.);
+        for (east, north, airspeed, _) in &samples {
+            let ground_speed = (east * east + north * north).sqrt();
+            if ground_speed == 0. { continue };
+            let (unit_east, unit_north) = (east / ground_speed, north / ground_speed);
+            let difference = ground_speed - airspeed;
+            ee += unit_east * unit_east;
+            en += unit_east * unit_north;
+            nn += unit_north * unit_north;
+            de += difference * unit_east;
+            dn += difference * unit_north;
+        }
+        let determinant = ee * nn - en * en;
+        if determinant.abs() < count / 10. { return None }; //the circle does not cover enough directions
+        let east = (de * nn - dn * en) / determinant;
+        let north = (dn * ee - de * en) / determinant;
+        Some(Wind::from_velocity(east, north, altitude, time))
+    }
+}
+
+/// Splits the fixes into complete 360 degree circles
+pub(crate) fn circles(fixes: &[Rc<Fix>]) -> Vec<&[Rc<Fix>]> {
+    let mut circles = vec![];
+    let mut circle_start = 0;
+    let mut turned = 0.;
+    for i in 2..fixes.len() {
+        turned += bearing_change(&fixes[i - 2], &fixes[i - 1], &fixes[i]);
+        if turned.abs() >= 360. {
+            circles.push(&fixes[circle_start..i]);
+            circle_start = i - 1;
+            turned = 0.;
+        }
+    }
+    circles
+}
+
+/// Vector average of the winds
+pub fn average(winds: &[Wind]) -> Option<Wind> {
+    if winds.is_empty() { return None };
+    let count = winds.len() as f32;
+    let (east, north) = winds.iter().map(|wind| wind.velocity()).fold((0., 0.), |acc, (east, north)| (acc.0 + east, acc.1 + north));
+    let altitude = (winds.iter().map(|wind| wind.altitude as i32).sum::<i32>() / winds.len() as i32) as Meters;
+    let time = (winds.iter().map(|wind| wind.time as u64).sum::<u64>() / winds.len() as u64) as Seconds;
+    Some(Wind::from_velocity(east / count, north / count, altitude, time))
+}
+
+/// Averages the winds in altitude bands of the given height, ordered from the lowest band
+pub fn by_altitude_band(winds: &[Wind], band: Meters) -> Vec<Wind> {
+    let mut bands: Vec<(i16, Vec<Wind>)> = vec![];
+    for wind in winds {
+        let band_number = wind.altitude.div_euclid(band);
+        match bands.iter_mut().find(|(number, _)| *number == band_number) {
+            Some((_, band_winds)) => band_winds.push(wind.clone()),
+            None => bands.push((band_number, vec![wind.clone()])),
+        }
+    }
+    bands.sort_by_key(|(number, _)| *number);
+    bands.into_iter().filter_map(|(number, band_winds)| {
+        let mut wind = average(&band_winds)?;
+        wind.altitude = number * band + band / 2;
+        Some(wind)
+    }).collect()
+}
+
+impl Flight {
+    /// The wind estimated in each thermal of the flight
+    pub fn winds(&self) -> Vec<Wind> {
+        self.segments.iter().filter_map(|segment| match segment {
+            Segment::Thermal(fixes) => Wind::from_thermal(fixes),
+            _ => None,
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Circles with a radius of 100 meters at 25 m/s in a wind from the west
+    fn circling_in_wind(wind_speed: Mps) -> Vec<Rc<Fix>> {
+        let (radius, speed) = (100., 25.);
+        (0..200).map(|second| {
+            let angle = second as f32 * speed / radius;
+            let east = radius * angle.sin() + wind_speed * second as f32;
+            let north = radius * angle.cos();
+            Rc::new(Fix {
+                timestamp: 40_000 + second,
+                latitude: 56. + north / 111_195.,
+                longitude: 9. + east / (111_195. * 56f32.to_radians().cos()),
+                alt: Some(1000),
+                alt_igc: 1000 + second as i16,
+                ..Default::default()
+            })
+        }).collect()
+    }
+
+    #[test]
+    fn wind_is_found_from_circle_drift() {
+        let wind = Wind::from_thermal(&circling_in_wind(5.)).expect("no wind found");
+        assert!((wind.speed - 5.).abs() < 0.5, "speed was {}", wind.speed);
+        assert!((wind.direction - 270.).abs() < 10., "direction was {}", wind.direction);
+    }
+
+    #[test]
+    fn wind_is_found_from_airspeed() {
+        let with_airspeed = |heading: bool| circling_in_wind(5.).iter().map(|fix| {
+            let angle = (fix.timestamp - 40_000) as f32 * 25. / 100.;
+            Rc::new(Fix {
+                tas: Some(90.),
+                heading: heading.then(|| (angle.cos().atan2(-angle.sin()).to_degrees().rem_euclid(360.)).round() as u16),
+                ..(**fix).clone()
+            })
+        }).collect::<Vec<Rc<Fix>>>();
+        for heading in [false, true] {
+            let fixes = with_airspeed(heading);
+            let circle = circles(&fixes)[0];
+            let wind = Wind::from_airspeed(circle, 1000, 0).expect("no wind found");
+            assert!((wind.speed - 5.).abs() < 0.5, "speed was {}", wind.speed);
+            assert!((wind.direction - 270.).abs() < 10., "direction was {}", wind.direction);
+        }
+    }
+
+    #[test]
+    fn no_wind_when_not_drifting() {
+        let wind = Wind::from_thermal(&circling_in_wind(0.)).expect("no wind found");
+        assert!(wind.speed < 0.5, "speed was {}", wind.speed);
+    }
+}
@@ -1,24 +1,20 @@
 #![windows_subsystem = "windows"]
 use std::fs;
+use std::path::Path;
 use iced::{Alignment, Application, Command, Element, executor, Theme, window};
 use iced::alignment::{Horizontal, Vertical};
-use iced::widget::{button, column, container, row, text, text_input};
+use iced::widget::{button, column, container, pick_list, row, scrollable, text, text_input};
 use iced::widget::progress_bar;
 use iced::Settings;
 use iced::window::{icon, Position};
-use igc_parser::records::util::{Date, Time};
+use igc_parser::records::util::Date;
 use image::ImageFormat;
-use quick_soar::{analysis, parser, PathStrategy};
-use quick_soar::analysis::calculation::Calculation;
+use quick_soar::{parser, pipeline, PathStrategy};
+use quick_soar::analysis::altitude::AltitudeSource;
+use quick_soar::analysis::segmenting::SegmentationPreset;
+use quick_soar::pipeline::{CompetitionDay, DayResult, PilotInput};
 use quick_soar::web_handling::soaringspot;
 use quick_soar::web_handling::soaringspot::SoaringSpot;
-use quick_soar::analysis::util::Offsetable;
-use quick_soar::excel::file_writer;
-use quick_soar::parser::task::Task;
-use quick_soar::parser::util::get_date;
-
-type Kph = f32;
-type FloatMeters = f32;
 
 pub fn main() -> iced::Result {
     let bytes = include_bytes!("qsicon.png");
@@ -27,7 +23,7 @@ pub fn main() -> iced::Result {
     AppState::run(Settings {
         id: None,
         window: window::Settings {
-            size: (400, 180),
+            size: (400, 380),
             position: Position::Centered,
             min_size: None,
             max_size: None,
@@ -60,6 +56,8 @@ enum ProgressState {
 #[derive(Clone, Debug)]
 enum Message {
     UrlChanged(String),
+    PresetSelected(SegmentationPreset),
+    AltitudeSelected(AltitudeSource),
     StartAnalysis, // Button
     GotSoaringspot(Result<SoaringSpot, String>),
     Downloading(Frac),
@@ -72,16 +70,14 @@ enum Message {
 
 struct AppState {
     input: String,
+    preset: SegmentationPreset,
+    altitude: AltitudeSource,
     progress: ProgressState,
     error_state: ErrorState,
     soaringspot: Option<SoaringSpot>,
     links: Vec<Option<String>>,
-    contents: Vec<String>,
-    date: Option<Date>,
-    start_times: Vec<Option<Time>>,
-    speeds: Vec<Option<Kph>>,
-    distances: Vec<Option<FloatMeters>>,
-    calculations: Vec<Calculation>,
+    day: Option<CompetitionDay>,
+    result: Option<DayResult>,
     path: String,
     analysis_path: Option<String>,
 }
@@ -110,16 +106,14 @@ impl Application for AppState {
         (
             Self {
                 input: "".to_string(),
+                preset: SegmentationPreset::Standard,
+                altitude: AltitudeSource::default(),
                 progress: ProgressState::NotStarted,
                 error_state: ErrorState::None,
                 soaringspot: None,
                 links: vec![],
-                contents: vec![],
-                date: None,
-                start_times: vec![],
-                speeds: vec![],
-                distances: vec![],
-                calculations: vec![],
+                day: None,
+                result: None,
                 path: PathStrategy::new().get_path(),
                 analysis_path: None,
             },
@@ -143,15 +137,33 @@ impl Application for AppState {
                 self.input = new_url;
                 Command::none()
             }
+            Message::PresetSelected(preset) => {
+                self.preset = preset;
+                Command::none()
+            }
+            Message::AltitudeSelected(altitude) => {
+                self.altitude = altitude;
+                Command::none()
+            }
             Message::StartAnalysis => {
-                self.contents.clear();
                 self.soaringspot = None;
-                self.date = None;
-                self.calculations.clear();
-                self.distances.clear();
-                self.speeds.clear();
-                self.start_times.clear();
+                self.day = None;
+                self.result = None;
                 self.links.clear();
+                if Path::new(&self.input).is_dir() { //analyse the local logs without SoaringSpot
+                    let day = match CompetitionDay::from_folder(&self.input, self.preset.config()) {
+                        Ok(day) if !day.inputs.is_empty() => day.with_altitude(self.altitude),
+                        _ => {
+                            self.progress = ProgressState::IncorrectURL;
+                            return Command::none()
+                        }
+                    };
+                    let length = day.inputs.len();
+                    self.result = Some(DayResult::new(day.date()));
+                    self.day = Some(day);
+                    self.progress = ProgressState::Analyzing(Frac(0, length));
+                    return Command::perform(async move { Frac(0, length) }, Message::Analyzed)
+                }
                 async fn contact_soaringspot(url: String) -> Result<SoaringSpot, String> {
                     let spot = SoaringSpot::new(url).await;
                     spot
@@ -193,40 +205,31 @@ impl Application for AppState {
             }
 
             Message::PreAnalysis(_) => {
-                let mut paths: Vec<_> = match fs::read_dir(&PathStrategy::new().get_path()) {
-                    Ok(paths) => paths,
-                    Err(_) => return Command::perform(async {GUIError::FailedDownloading}, Message::Error),
-                }
-                    .filter_map(|r| r.ok())
-                    .filter(|dir| dir.path().is_file())
-                    .collect();
-                paths.sort_by_key(|dir| dir.path());
-                let contents = paths.into_iter().map(|path| {
-                    parser::util::get_contents(path.path().display().to_string().as_str()).ok()
-                }).collect::<Option<Vec<String>>>();
-
-                let contents = match contents {
-                    Some(contents) => contents,
-                    None => return Command::perform(async {GUIError::FailedDownloading}, Message::Error),
-                };
-
                 async fn pre_analysis(length: usize) -> Frac {
                     Frac(0, length)
                 }
 
                 let spot = self.soaringspot.as_ref().expect("unreachable");
-                let date = get_date(contents[0].as_str()).unwrap_or(Date { d: 1, m :1, y: 1});
-                let start_times = spot.get_start_times();
-                let speeds = spot.get_speeds();
-                let distances = spot.get_distances();
-                self.date = Some(date);
-                self.start_times = start_times;
-                self.speeds = speeds;
-                self.distances = distances;
-                self.contents = contents;
-                self.progress = ProgressState::Analyzing(Frac(0, self.contents.len()));
-                println!("length of contents is {}", self.contents.len());
-                Command::perform(pre_analysis(self.contents.len()), Message::Analyzed)
+                let inputs = spot.get_start_times().into_iter()
+                    .zip(spot.get_speeds())
+                    .zip(spot.get_distances())
+                    .enumerate()
+                    .filter_map(|(index, ((start_time, speed), distance))| {
+                        let name = format!("{:0>3}.igc", index + 1);
+                        let contents = parser::util::get_contents(format!("{}{}", &self.path, name).as_str()).ok()?;
+                        Some(PilotInput { name, contents, start_time, speed, distance })
+                    })
+                    .collect::<Vec<PilotInput>>();
+                if inputs.is_empty() {
+                    return Command::perform(async {GUIError::FailedDownloading}, Message::Error)
+                }
+
+                let day = CompetitionDay::new(inputs, self.preset.config()).with_altitude(self.altitude);
+                let length = day.inputs.len();
+                self.result = Some(DayResult::new(day.date()));
+                self.day = Some(day);
+                self.progress = ProgressState::Analyzing(Frac(0, length));
+                Command::perform(pre_analysis(length), Message::Analyzed)
             }
 
             Message::Analyzed(frac) if frac.is_max() => {
@@ -235,37 +238,21 @@ impl Application for AppState {
             }
 
             Message::Analyzed(Frac(analyzed, total)) => {
-                let content = self.contents[analyzed].clone();
-                let speed = self.speeds[analyzed].clone();
-                let dist = self.distances[analyzed].clone();
-                let start_time = self.start_times[analyzed].as_ref();
-                let calc: Option<Calculation> = {
-                    match Task::parse(&content).ok() {
-                        None => None,
-                        Some(task) => {
-                            let fixes = parser::util::get_fixes(&content);
-                            let flight = analysis::segmenting::Flight::make(fixes);
-                            let pilot_info = parser::pilot_info::PilotInfo::parse(&content);
-                            if let (Some(flight), Ok(pilot_info)) = (flight, pilot_info) {
-                                let time_zone = pilot_info.time_zone;
-                                let start_time = match start_time.map(|s| s.clone()) { None => None, Some(mut time) => { time.offset(-time_zone); Some(time.seconds_since_midnight()) } };
-                                let calculation = Calculation::new(task, flight, pilot_info, start_time, speed, dist);
-                                calculation
-                            } else {
-                                None
-                            }
-                        },
-                    }
-                };
-                if let Some(calc) = calc {self.calculations.push(calc)}
+                let day = self.day.as_ref().expect("unreachable");
+                let input = &day.inputs[analyzed];
+                let result = self.result.as_mut().expect("unreachable");
+                result.push(input, &day.task, pipeline::analyze(input, &day.config, &day.task, day.altitude));
                 self.progress = ProgressState::Analyzing(Frac(analyzed, total));
                 Command::perform(async move { Frac(analyzed + 1, total) }, Message::Analyzed)
             }
 
             Message::PostAnalysis(_) => {
+                let result = self.result.as_ref().expect("unreachable");
+                if result.calculations.is_empty() {
+                    return Command::perform(async {GUIError::NoFlightsAnalyzed}, Message::Error)
+                }
                 self.progress = ProgressState::Finished;
-                let some_calc = self.calculations.first().expect("calculations are empty, this should be unreachable");
-                let date = self.date.clone().unwrap_or(Date { d: 0, m: 0, y: 0});
+                let date = result.date.clone().unwrap_or(Date { d: 0, m: 0, y: 0});
                 let class: Option<String> = {
                     let url = self.input.clone();
                     let parts = url.split("/").collect::<Vec<&str>>();
@@ -284,9 +271,9 @@ impl Application for AppState {
                 println!("analysis path is {}", analysis_path);
                 let analysis_path = soaringspot::make_file_name_unique(analysis_path.as_str());
                 println!("analysis path is {}", analysis_path);
-                let _ = soaringspot::delete_files_in_dir(&self.path);
+                if self.soaringspot.is_some() { let _ = soaringspot::delete_files_in_dir(&self.path); }
                 fs::create_dir(format!("{}/analysis", &self.path)).unwrap_or(());
-                let _ = file_writer::make_excel_file(&analysis_path, some_calc.get_task(), &self.calculations, date);
+                let _ = result.write_excel(&analysis_path);
                 self.analysis_path = Some(analysis_path);
                 Command::none()
             }
@@ -299,25 +286,35 @@ impl Application for AppState {
     }
 
     fn view(&self) -> Element<Self::Message> {
-        let AppState { input, progress, .. } = self;
+        let AppState { input, preset, altitude, progress, .. } = self;
 
         let (progress_percentage, progress_text) = match progress {
-            ProgressState::NotStarted => (0., "Enter URL and start analysis".to_string()),
+            ProgressState::NotStarted => (0., "Enter URL or folder and start analysis".to_string()),
             ProgressState::Downloading(frac) => (frac.to_percentage(), format!("Downloading: {}/{}", frac.0, frac.1)),
             ProgressState::Analyzing(frac) => (frac.to_percentage(), format!("Analyzing: {}/{}", frac.0, frac.1)),
             ProgressState::Finished => (100., "Now you can open the analysis".to_string()),
-            ProgressState::IncorrectURL => (0., "URL must be from a SoaringSpot competition day".to_string()),
+            ProgressState::IncorrectURL => (0., "Enter a SoaringSpot day or an IGC folder".to_string()),
             ProgressState::Error(_) => (0., "Error".to_string()), // TODO expand this
         };
 
         let input_field = text_input::TextInput::new("", input).size(16).on_input(|s| Message::UrlChanged(s));
 
-        let txt = text::Text::new("Enter URL: ").size(20).vertical_alignment(Vertical::Center);
+        let txt = text::Text::new("URL or folder: ").size(20).vertical_alignment(Vertical::Center);
         let url_row = row![
             txt,
             input_field
         ].align_items(Alignment::Center).padding(10);
 
+        let preset_row = row![
+            text("Segmentation: ").size(20).vertical_alignment(Vertical::Center),
+            pick_list(&SegmentationPreset::ALL[..], Some(*preset), Message::PresetSelected).text_size(16)
+        ].align_items(Alignment::Center).padding(10);
+
+        let altitude_row = row![
+            text("Altitude: ").size(20).vertical_alignment(Vertical::Center),
+            pick_list(&AltitudeSource::ALL[..], Some(*altitude), Message::AltitudeSelected).text_size(16)
+        ].align_items(Alignment::Center).padding(10);
+
         let progress_text = row![
             text(progress_text).size(20).vertical_alignment(Vertical::Center)
         ].padding(10);
@@ -350,10 +347,21 @@ impl Application for AppState {
             open_file_button
         ].spacing(10).padding(10);
 
+        let failures = self.result.as_ref().map_or(vec![], |result| result.failures.iter().flat_map(|failure| {
+            let diagnostics = failure.diagnostics.iter().map(|diagnostic| format!("    {}", diagnostic));
+            std::iter::once(format!("{}: {}", failure.name, failure.error)).chain(diagnostics)
+        }).map(|line| text(line).size(12).into()).collect::<Vec<Element<Self::Message>>>());
+        let failure_row = row![
+            scrollable(column(failures)).height(90).width(380)
+        ].padding(10);
+
         let col = column(vec![url_row.into(),
+                              preset_row.into(),
+                              altitude_row.into(),
                               progress_text.into(),
                               progress_row.into(),
-                              button_row.into()]);
+                              button_row.into(),
+                              failure_row.into()]);
 
         container(col).into()
     }
@@ -383,6 +391,7 @@ enum FileProgress {
 enum GUIError {
     FailedDownloading,
     FailedWriting,
+    NoFlightsAnalyzed,
 }
 
 #[derive(Debug)]
@@ -0,0 +1,142 @@
+use std::{env, process};
+use std::path::Path;
+use igc_parser::records::util::Date;
+use quick_soar::PathStrategy;
+use quick_soar::analysis::altitude::AltitudeSource;
+use quick_soar::analysis::segmenting::SegmentationPreset;
+use quick_soar::parser::task::Task;
+use quick_soar::pipeline::{CompetitionDay, TaskSource};
+
+const USAGE: &str = "usage: quick_soar <soaringspot results url | folder of igc files | igc files...> [--output <path>] [--format xlsx|csv] [--segmentation standard|coarse] [--altitude pressure|gnss|qnh] [--task | --check-task <cup or tsk file>]";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Xlsx,
+    Csv,
+}
+
+impl Format {
+    fn extension(&self) -> &str {
+        match self {
+            Format::Xlsx => "xlsx",
+            Format::Csv => "csv",
+        }
+    }
+}
+
+struct Args {
+    sources: Vec<String>,
+    output: Option<String>,
+    format: Format,
+    preset: SegmentationPreset,
+    altitude: AltitudeSource,
+    task: Option<(String, bool)>, //the task file and if the declarations should only be validated
+}
+
+impl Args {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Self, String> {
+        let mut sources = vec![];
+        let mut output = None;
+        let mut format = None;
+        let mut preset = SegmentationPreset::Standard;
+        let mut altitude = AltitudeSource::default();
+        let mut task = None;
+        while let Some(arg) = args.next() {
+            let mut value = |name: &str| args.next().ok_or(format!("missing value for {}", name));
+            match arg.as_str() {
+                "-o" | "--output" => output = Some(value(&arg)?),
+                "-f" | "--format" => format = Some(match value(&arg)?.to_lowercase().as_str() {
+                    "xlsx" => Format::Xlsx,
+                    "csv" => Format::Csv,
+                    other => return Err(format!("unknown format {}", other)),
+                }),
+                "-s" | "--segmentation" => preset = value(&arg)?.parse()?,
+                "-a" | "--altitude" => altitude = value(&arg)?.parse()?,
+                "-t" | "--task" => task = Some((value(&arg)?, false)),
+                "--check-task" => task = Some((value(&arg)?, true)),
+                "-h" | "--help" => return Err("".to_string()),
+                _ if arg.starts_with('-') => return Err(format!("unknown option {}", arg)),
+                _ => sources.push(arg),
+            }
+        }
+        if sources.is_empty() { return Err("no url, folder or files given".to_string()) };
+        let format = match (format, &output) {
+            (Some(format), _) => format,
+            (None, Some(output)) if output.ends_with(".csv") => Format::Csv,
+            (None, _) => Format::Xlsx,
+        };
+        Ok(Self { sources, output, format, preset, altitude, task })
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let args = match Args::parse(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(error) => {
+            if !error.is_empty() { eprintln!("{}", error) };
+            eprintln!("{}", USAGE);
+            process::exit(2)
+        }
+    };
+
+    let source = &args.sources[0];
+    let day = if args.sources.len() > 1 || Path::new(source).is_file() {
+        CompetitionDay::from_files(&args.sources, args.preset.config())
+    } else if Path::new(source).is_dir() {
+        CompetitionDay::from_folder(source, args.preset.config())
+    } else {
+        let dir = format!("{}cli/", PathStrategy::new().get_path());
+        CompetitionDay::from_soaringspot(source, &dir, args.preset.config(), |progress| {
+            eprintln!("Downloading: {}/{}", progress.done, progress.total)
+        }).await
+    };
+    let day = match day {
+        Ok(day) if !day.inputs.is_empty() => day,
+        Ok(_) => fail("no IGC files found"),
+        Err(error) => fail(&error),
+    };
+    let day = match &args.task {
+        Some((path, validate)) => match (Task::from_file(path), validate) {
+            (Ok(task), true) => day.with_task(TaskSource::Validate(task)),
+            (Ok(task), false) => day.with_task(TaskSource::Override(task)),
+            (Err(error), _) => fail(&format!("unable to read the task in {}: {}", path, error)),
+        },
+        None => day,
+    };
+    let day = day.with_altitude(args.altitude);
+
+    let result = day.run(|progress| eprintln!("Analyzing: {}/{}", progress.done, progress.total));
+    for failure in &result.failures {
+        eprintln!("Unable to analyze {}: {}", failure.name, failure.error);
+        for diagnostic in &failure.diagnostics {
+            eprintln!("  {}", diagnostic);
+        }
+    }
+    if result.calculations.is_empty() { fail("no flights could be analyzed") };
+
+    let date = result.date.clone().unwrap_or(Date { d: 0, m: 0, y: 0 });
+    let output = args.output.clone().unwrap_or_else(|| {
+        let class = class_from_url(source).unwrap_or("".to_string());
+        format!("QS-{}-{}-{}-{}.{}", class, date.d, date.m, date.y, args.format.extension())
+    });
+    let written = match args.format {
+        Format::Xlsx => result.write_excel(&output),
+        Format::Csv => result.write_csv(&output),
+    };
+    match written {
+        Ok(_) => println!("{}", output),
+        Err(error) => fail(&format!("failed writing {}: {}", output, error)),
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1)
+}
+
+fn class_from_url(url: &str) -> Option<String> {
+    let parts = url.split('/').collect::<Vec<&str>>();
+    let index = parts.iter().position(|p| p.starts_with("results"))?;
+    parts.get(index + 1).map(|class| class.to_string())
+}
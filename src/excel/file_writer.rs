@@ -1,7 +1,10 @@
 use std::collections::HashMap;
 use std::fs;
+use crate::analysis::altitude::AltitudeSource;
 use crate::analysis::calculation::{Calculation, TaskPiece};
-use crate::analysis::util::Offsetable;
+use crate::analysis::thermal;
+use crate::analysis::thermal::Thermal;
+use crate::analysis::util::{time_of_day, Offsetable};
 use crate::parser::task::Task;
 use igc_parser::records::util::Date;
 use umya_spreadsheet::*;
@@ -42,12 +45,13 @@ pub fn make_excel_file(path: &str, task: &Task, data: &Vec<Calculation>, date: D
     }).collect::<Result<_, ExcelError>>()?;
 
     let columns = all::<ColumnHeader>().collect::<Vec<ColumnHeader>>();
+    let altitude = altitude_source(data);
 
     let formatted_data = format_data(data, TaskPiece::EntireTask);
 
     for (index, column) in columns.iter().enumerate() {
         let coord = CellCoordinates { row: 2, col: (index + 1) as u32 };
-        add_column_to_worksheet(book.get_sheet_mut(&1).expect("unreachable"), column, formatted_data.get(column).expect("unreachable"), coord);
+        add_column_to_worksheet(book.get_sheet_mut(&1).expect("unreachable"), column, altitude, formatted_data.get(column).expect("unreachable"), coord);
         book.get_sheet_mut(&1).expect("unreachable").get_row_dimension_mut(&2).set_height(120.);
     }
 
@@ -59,14 +63,233 @@ pub fn make_excel_file(path: &str, task: &Task, data: &Vec<Calculation>, date: D
         ws.get_row_dimension_mut(&2).set_height(120.);
         for (index, column) in columns.iter().enumerate() {
             let coord = CellCoordinates { row: 2, col: (index + 1) as u32 };
-            add_column_to_worksheet(ws, column, formatted_data.get(column).expect("unreachable"), coord);
+            add_column_to_worksheet(ws, column, altitude, formatted_data.get(column).expect("unreachable"), coord);
         }
     }
 
+    let day_thermals = data.iter().flat_map(|calc| calc.thermals(TaskPiece::EntireTask)).collect::<Vec<Thermal>>();
+    let best_climb = thermal::best_climb(&day_thermals);
+    for calc in data {
+        let name = format!("Thermals {}", calc.pilot_info.comp_id);
+        let ws = match book.new_sheet(name.as_str()) {
+            Ok(ws) => ws,
+            Err(_) => continue, //the callsign is used twice
+        };
+        add_thermal_sheet(ws, calc, best_climb);
+    }
+    for calc in data {
+        let name = format!("Glides {}", calc.pilot_info.comp_id);
+        let ws = match book.new_sheet(name.as_str()) {
+            Ok(ws) => ws,
+            Err(_) => continue, //the callsign is used twice
+        };
+        add_glide_sheet(ws, calc);
+    }
+
+    let settings = match book.new_sheet("Settings") {
+        Ok(ws) => ws,
+        Err(_) => return Err(ExcelError::Excel),
+    };
+    add_settings_sheet(settings, data);
+
+    let pilots = match book.new_sheet("Pilots") {
+        Ok(ws) => ws,
+        Err(_) => return Err(ExcelError::Excel),
+    };
+    add_pilot_sheet(pilots, data);
+
     writer::xlsx::write(&book, path)?;
     Ok(())
 }
 
+/// Writes the same columns as the workbook with one row per pilot and task piece
+pub fn make_csv_file(path: &str, task: &Task, data: &Vec<Calculation>) -> Result<(), ExcelError> {
+    let columns = all::<ColumnHeader>().collect::<Vec<ColumnHeader>>();
+    let mut lines = vec![];
+    let altitude = altitude_source(data);
+    let header = columns.iter().map(|column| match column.unit(altitude) {
+        Some(unit) => format!("{} {}", column.to_string(), unit),
+        None => column.to_string().to_string(),
+    }).collect::<Vec<String>>();
+    lines.push(format!("Task piece,{}", header.join(",")));
+
+    let task_pieces = std::iter::once(TaskPiece::EntireTask)
+        .chain(task.points.windows(2).enumerate().map(|(index, _)| TaskPiece::Leg(index)));
+    for task_piece in task_pieces {
+        let name = match task_piece {
+            TaskPiece::EntireTask => "Entire flight".to_string(),
+            TaskPiece::Leg(index) => format!("Leg {}", index + 1),
+        };
+        let formatted_data = format_data(data, task_piece);
+        for row in 0..data.len() {
+            let cells = columns.iter().map(|column| {
+                match formatted_data.get(column).and_then(|cells| cells.get(row)).map(|cell| &cell.value) {
+                    Some(CellValue::Float(val)) => format!("{:.2}", val),
+                    Some(CellValue::Int(val)) => val.to_string(),
+                    Some(CellValue::String(s)) => format!("\"{}\"", s.replace('"', "\"\"")),
+                    Some(CellValue::None) | None => "".to_string(),
+                }
+            }).collect::<Vec<String>>();
+            lines.push(format!("{},{}", name, cells.join(",")));
+        }
+    }
+    fs::write(path, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+/// The altitude the day was analysed with, every pilot of a day uses the same
+fn altitude_source(data: &[Calculation]) -> AltitudeSource {
+    data.first().map_or(AltitudeSource::default(), |calc| calc.altitude.source)
+}
+
+/// Records the segmentation and altitude settings used for each pilot
+fn add_settings_sheet(worksheet: &mut Worksheet, data: &Vec<Calculation>) {
+    let headers = ["Callsign", "Turn [deg]", "Turn window [s]", "Connect time [s]", "Thermal backset [s]", "Try time [s]",
+        "Altitude", "Altitude offset [m]", "Estimated QNH [hPa]"];
+    let rows = data.iter().map(|calc| {
+        let config = calc.segmentation_config();
+        vec![
+            CellValue::String(calc.pilot_info.comp_id.clone()),
+            CellValue::Float(config.degree_boundary),
+            CellValue::Float(config.time_window as f32),
+            CellValue::Float(config.connect_time as f32),
+            CellValue::Float(config.thermal_backset as f32),
+            CellValue::Float(config.try_time as f32),
+            CellValue::String(calc.altitude.source.to_string()),
+            CellValue::Int(calc.altitude.offset),
+            match calc.altitude.qnh() {
+                None => CellValue::None,
+                Some(qnh) => CellValue::Float(qnh),
+            },
+        ]
+    }).collect();
+    add_table(worksheet, &headers, rows);
+}
+
+/// Records the header information of each pilot's log
+fn add_pilot_sheet(worksheet: &mut Worksheet, data: &Vec<Calculation>) {
+    let headers = ["Callsign", "Pilot", "Registration", "Class", "Glider", "Logger", "GPS", "Firmware", "Hardware", "Time zone",
+        "A record logger", "Logger ID", "IGC security", "Security issues"];
+    let optional = |value: &Option<String>| match value {
+        None => CellValue::None,
+        Some(value) => CellValue::String(value.clone()),
+    };
+    let rows = data.iter().map(|calc| {
+        let info = &calc.pilot_info;
+        vec![
+            CellValue::String(info.comp_id.clone()),
+            optional(&info.pilot),
+            optional(&info.registration),
+            optional(&info.comp_class),
+            CellValue::String(info.glider_type.clone()),
+            optional(&info.logger_type),
+            optional(&info.gps),
+            optional(&info.firmware),
+            optional(&info.hardware),
+            CellValue::Int(info.time_zone as i16),
+            optional(&calc.security.as_ref().and_then(|security| security.logger())),
+            optional(&calc.security.as_ref().and_then(|security| security.logger_id.clone())),
+            optional(&calc.security.as_ref().map(|security| security.status().to_string())),
+            optional(&calc.security.as_ref().map(|security| {
+                security.issues.iter().map(|issue| issue.to_string()).collect::<Vec<String>>().join("; ")
+            })),
+        ]
+    }).collect();
+    add_table(worksheet, &headers, rows);
+}
+
+/// Lists every thermal of the pilot's task
+fn add_thermal_sheet(worksheet: &mut Worksheet, calc: &Calculation, best_climb: Option<f32>) {
+    let unit = calc.altitude.source.unit();
+    let (entry_alt, exit_alt) = (format!("Entry altitude {}", unit), format!("Exit altitude {}", unit));
+    let headers = ["#", "Entry time (Local)", "Exit time (Local)", entry_alt.as_str(), exit_alt.as_str(), "Height gain [m]",
+        "Time [s]", "Average climb [m/s]", "Peak climb [m/s]", "Drift [m]", "Drift direction [deg]", "Climb efficiency [%]"];
+    let utc_offset = calc.pilot_info.time_zone;
+    let rows = calc.thermals(TaskPiece::EntireTask).into_iter().enumerate().map(|(index, thermal)| {
+        vec![
+            CellValue::Int((index + 1) as i16),
+            local_time(thermal.entry_time, utc_offset),
+            local_time(thermal.exit_time, utc_offset),
+            CellValue::Int(thermal.entry_alt),
+            CellValue::Int(thermal.exit_alt),
+            CellValue::Int(thermal.height_gain),
+            CellValue::Int(thermal.time as i16),
+            CellValue::Float(thermal.avg_climb),
+            CellValue::Float(thermal.peak_climb),
+            CellValue::Int(thermal.drift.round() as i16),
+            CellValue::Int(thermal.drift_direction.round() as i16 % 360),
+            match best_climb.and_then(|best_climb| thermal.efficiency(best_climb)) {
+                None => CellValue::None,
+                Some(efficiency) => CellValue::Float(efficiency),
+            },
+        ]
+    }).collect();
+    add_table(worksheet, &headers, rows);
+}
+
+/// Lists every glide of the pilot's task
+fn add_glide_sheet(worksheet: &mut Worksheet, calc: &Calculation) {
+    let unit = calc.altitude.source.unit();
+    let (start_alt, end_alt) = (format!("Start altitude {}", unit), format!("End altitude {}", unit));
+    let headers = ["#", "Start time (Local)", "End time (Local)", start_alt.as_str(), end_alt.as_str(), "Time [s]",
+        "Distance [km]", "Flown distance [km]", "Glide ratio [x:1]", "Average speed [km/h]", "True airspeed [km/h]",
+        "Netto [m/s]", "Course deviation [deg]", "Distance from task line [km]"];
+    let utc_offset = calc.pilot_info.time_zone;
+    let optional = |value: Option<f32>| match value {
+        None => CellValue::None,
+        Some(value) => CellValue::Float(value),
+    };
+    let rows = calc.glides(TaskPiece::EntireTask).into_iter().enumerate().map(|(index, glide)| {
+        vec![
+            CellValue::Int((index + 1) as i16),
+            local_time(glide.start_time, utc_offset),
+            local_time(glide.end_time, utc_offset),
+            CellValue::Int(glide.start_alt),
+            CellValue::Int(glide.end_alt),
+            CellValue::Int(glide.time as i16),
+            CellValue::Float(glide.distance / 1000.),
+            CellValue::Float(glide.flown_distance / 1000.),
+            optional(glide.glide_ratio),
+            CellValue::Float(glide.avg_speed),
+            optional(glide.airspeed),
+            CellValue::Float(glide.netto),
+            optional(glide.course_deviation),
+            optional(glide.line_deviation.map(|deviation| deviation / 1000.)),
+        ]
+    }).collect();
+    add_table(worksheet, &headers, rows);
+}
+
+fn local_time(seconds: u32, utc_offset: i8) -> CellValue {
+    match time_of_day(seconds) {
+        Some(mut time) => {
+            time.offset(utc_offset);
+            CellValue::String(format!("{:0>2}:{:0>2}:{:0>2}", time.h, time.m, time.s))
+        }
+        None => CellValue::None,
+    }
+}
+
+fn add_table(worksheet: &mut Worksheet, headers: &[&str], rows: Vec<Vec<CellValue>>) {
+    for (index, header) in headers.iter().enumerate() {
+        let cell = worksheet.get_cell_mut(((index + 1) as u32, 1));
+        cell.set_value_from_string(*header);
+        cell.get_style_mut().get_font_mut().set_name("Times New Roman").set_font_size(FontSize::default().set_val(10.).clone()).set_bold(true);
+    }
+    for (row_index, row) in rows.iter().enumerate() {
+        for (col_index, value) in row.iter().enumerate() {
+            let cell = worksheet.get_cell_mut(((col_index + 1) as u32, (row_index + 2) as u32));
+            match value {
+                CellValue::Float(val) => { cell.set_value_number(*val); }
+                CellValue::Int(val) => { cell.set_value_number(*val as f64); }
+                CellValue::String(s) => { cell.set_value_from_string(s); }
+                CellValue::None => { cell.set_value_from_string("---"); }
+            }
+            cell.get_style_mut().get_font_mut().set_name("Times New Roman").set_font_size(FontSize::default().set_val(10.).clone());
+        }
+    }
+}
+
 fn add_non_data_formatting(worksheet: &mut Worksheet, date: &str, task_piece: TaskPiece) {
     let task_piece_string = match task_piece {
         TaskPiece::EntireTask => "Entire flight".to_string(),
@@ -81,10 +304,23 @@ fn add_non_data_formatting(worksheet: &mut Worksheet, date: &str, task_piece: Ta
     task_piece_cell.set_value_from_string(task_piece_string);
     task_piece_cell.get_style_mut().set_background_color_solid("FF9999FF").get_font_mut().set_name("Times New Roman").set_font_size(FontSize::default().set_val(10.).clone()).set_bold(true);
     task_piece_cell.get_style_mut().get_alignment_mut().set_horizontal(HorizontalAlignmentValues::Center);
-    worksheet.add_merge_cells("B1:S1");
+    let last_column = column_name(all::<ColumnHeader>().count() as u32);
+    worksheet.add_merge_cells(format!("B1:{}1", last_column).as_str());
+}
+
+/// Spreadsheet name of a 1-indexed column, 1 is A and 27 is AA
+fn column_name(index: u32) -> String {
+    let mut index = index;
+    let mut name = String::new();
+    while index > 0 {
+        let remainder = ((index - 1) % 26) as u8;
+        name.insert(0, (b'A' + remainder) as char);
+        index = (index - 1) / 26;
+    }
+    name
 }
 
-fn add_column_to_worksheet<T: Into<CellCoordinates>>(worksheet: &mut Worksheet, column: &ColumnHeader, data: &Vec<DataCell>, top_coord: T) {
+fn add_column_to_worksheet<T: Into<CellCoordinates>>(worksheet: &mut Worksheet, column: &ColumnHeader, altitude: AltitudeSource, data: &Vec<DataCell>, top_coord: T) {
     let top_coord = top_coord.into();
     let desc_cell = worksheet.get_cell_mut((top_coord.col, top_coord.row)).set_value_from_string(column.to_string());
     desc_cell.get_style_mut().get_alignment_mut().set_horizontal(HorizontalAlignmentValues::Center);
@@ -93,7 +329,7 @@ fn add_column_to_worksheet<T: Into<CellCoordinates>>(worksheet: &mut Worksheet,
     let top_coord = CellCoordinates { row: top_coord.row + 1, col: top_coord.col };
     let unit_cell = worksheet.get_cell_mut((top_coord.col, top_coord.row));
         unit_cell.get_style_mut().get_font_mut().set_name("Times New Roman").set_font_size(FontSize::default().set_val(10.).clone()).set_bold(true);
-        unit_cell.set_value_from_string(column.unit().unwrap_or(""));
+        unit_cell.set_value_from_string(column.unit(altitude).unwrap_or(""));
         unit_cell.get_style_mut().get_alignment_mut().set_horizontal(HorizontalAlignmentValues::Center);
     let top_coord = CellCoordinates { row: top_coord.row + 2, col: top_coord.col }; //this moves down, and creates a gap
     for (index, d) in data.iter().enumerate() {
@@ -151,6 +387,8 @@ enum ColumnHeader {
     Ranking,
     Airplane,
     Callsign,
+    Pilot,
+    Class,
     Distance,
     StartTime,
     FinishTime,
@@ -163,10 +401,20 @@ enum ColumnHeader {
     CruiseDistance,
     ExcessDistance,
     Speed,
+    ComputedSpeed,
+    ComputedDistance,
     TurningPercentage,
     ThermalAltLoss,
     ThermalDrift,
     PercentBelow500,
+    WindSpeed,
+    WindDirection,
+    CirclingRadius,
+    CircleTime,
+    BankAngle,
+    StartViolations,
+    FinishBelowMinimum,
+    Security,
 }
 
 impl ColumnHeader {
@@ -174,11 +422,13 @@ impl ColumnHeader {
         use ColumnHeader::*;
         match self {
             StartTime => "Start time (Local)",
-            StartAlt => "Start altitude (MSL)",
-            FinishAlt => "Finish altitude (MSL)",
+            StartAlt => "Start altitude",
+            FinishAlt => "Finish altitude",
             Ranking => "Ranking",
             Airplane => "Airplane",
             Callsign => "Callsign",
+            Pilot => "Pilot",
+            Class => "Class",
             Distance => "Distance flown",
             FinishTime => "Finish time (Local)",
             ClimbRate => "Average rate of climb",
@@ -188,21 +438,34 @@ impl ColumnHeader {
             GlideRatio => "Average glide ratio",
             ExcessDistance => "Excess distance covered",
             Speed => "XC Speed",
+            ComputedSpeed => "Computed XC speed",
+            ComputedDistance => "Computed distance",
             TurningPercentage => "Circling percentage",
             ThermalAltLoss => "Thermal altitude loss",
             PercentBelow500 => "Percentage below 500 QFE",
-            ThermalDrift => "Task flown in thermals"
+            ThermalDrift => "Task flown in thermals",
+            WindSpeed => "Average wind speed",
+            WindDirection => "Average wind direction",
+            CirclingRadius => "Average circling radius",
+            CircleTime => "Average time per circle",
+            BankAngle => "Average bank angle",
+            StartViolations => "Start violations",
+            FinishBelowMinimum => "Finish below minimum",
+            Security => "IGC security",
         }
     }
 
-    fn unit(&self) -> Option<&str> {
+    fn unit(&self, altitude: AltitudeSource) -> Option<&str> {
         use ColumnHeader::*;
         match self {
-            Ranking | Airplane | Callsign | StartTime | FinishTime | GlideRatio => None,
-            Distance => Some("[km]"),
-            StartAlt | FinishAlt => Some("[m]"),
+            Ranking | Airplane | Callsign | Pilot | Class | StartTime | FinishTime | GlideRatio | StartViolations | Security => None,
+            Distance | ComputedDistance => Some("[km]"),
+            StartAlt | FinishAlt => Some(altitude.unit()),
             ClimbRate => Some("[m/s]"),
-            CruiseSpeed | Speed | ClimbSpeed => Some("[km/h]"),
+            CruiseSpeed | Speed | ComputedSpeed | ClimbSpeed | WindSpeed => Some("[km/h]"),
+            WindDirection | BankAngle => Some("[deg]"),
+            CirclingRadius | FinishBelowMinimum => Some("[m]"),
+            CircleTime => Some("[s]"),
             CruiseDistance => Some("[km]"),
             ExcessDistance | ThermalAltLoss | TurningPercentage | PercentBelow500 | ThermalDrift => Some("[%]"),
         }
@@ -211,7 +474,8 @@ impl ColumnHeader {
     fn colorizable(&self) -> Colorizable {
         use ColumnHeader::*;
         match self {
-            Ranking | Airplane  | Callsign | Distance | StartTime | FinishTime => Colorizable::Never,
+            Ranking | Airplane  | Callsign | Pilot | Class | Distance | StartTime | FinishTime | WindSpeed | WindDirection
+            | CirclingRadius | CircleTime | BankAngle | ComputedSpeed | ComputedDistance | StartViolations | FinishBelowMinimum | Security => Colorizable::Never,
             StartAlt => Colorizable::Always,
             ClimbRate | ClimbSpeed | CruiseSpeed | CruiseDistance | GlideRatio
             | ExcessDistance | Speed | TurningPercentage | ThermalAltLoss | PercentBelow500 | ThermalDrift | FinishAlt  => Colorizable::OnlyOnFinish
@@ -247,6 +511,18 @@ impl ColumnHeader {
                     CellValue::String(pilot_info.comp_id.clone())
                 }).collect::<Vec<CellValue>>()
             }
+            Pilot => {
+                data.iter().map(|d| match &d.pilot_info.pilot {
+                    None => CellValue::None,
+                    Some(pilot) => CellValue::String(pilot.clone()),
+                }).collect::<Vec<CellValue>>()
+            }
+            Class => {
+                data.iter().map(|d| match &d.pilot_info.comp_class {
+                    None => CellValue::None,
+                    Some(class) => CellValue::String(class.clone()),
+                }).collect::<Vec<CellValue>>()
+            }
             Distance => {
                 data.iter().map(|d| {
                     let calc = &d;
@@ -375,6 +651,30 @@ impl ColumnHeader {
                     }
                 }).collect::<Vec<CellValue>>()
             }
+            ComputedSpeed => {
+                data.iter().map(|d| {
+                    let value = match task_piece {
+                        TaskPiece::EntireTask => d.computed_speed(),
+                        TaskPiece::Leg(_) => None,
+                    };
+                    match value {
+                        None => CellValue::None,
+                        Some(value) => CellValue::Float(value)
+                    }
+                }).collect::<Vec<CellValue>>()
+            }
+            ComputedDistance => {
+                data.iter().map(|d| {
+                    let value = match task_piece {
+                        TaskPiece::EntireTask => d.scored_distance(),
+                        TaskPiece::Leg(_) => None,
+                    };
+                    match value {
+                        None => CellValue::None,
+                        Some(value) => CellValue::Float(value / 1000.)
+                    }
+                }).collect::<Vec<CellValue>>()
+            }
             TurningPercentage => {
                 data.iter().map(|d| {
                     let calc = &d;
@@ -415,6 +715,74 @@ impl ColumnHeader {
                     }
                 }).collect::<Vec<CellValue>>()
             }
+            WindSpeed => {
+                data.iter().map(|d| {
+                    let calc = &d;
+                    let value = calc.wind(task_piece);
+                    match value {
+                        None => CellValue::None,
+                        Some(wind) => CellValue::Float(wind.speed * 3.6)
+                    }
+                }).collect::<Vec<CellValue>>()
+            }
+            WindDirection => {
+                data.iter().map(|d| {
+                    let calc = &d;
+                    let value = calc.wind(task_piece);
+                    match value {
+                        None => CellValue::None,
+                        Some(wind) => CellValue::Int(wind.direction.round() as i16 % 360)
+                    }
+                }).collect::<Vec<CellValue>>()
+            }
+            CirclingRadius => {
+                data.iter().map(|d| {
+                    let calc = &d;
+                    let value = calc.circling_radius(task_piece);
+                    match value {
+                        None => CellValue::None,
+                        Some(value) => CellValue::Float(value)
+                    }
+                }).collect::<Vec<CellValue>>()
+            }
+            CircleTime => {
+                data.iter().map(|d| {
+                    let calc = &d;
+                    let value = calc.circle_time(task_piece);
+                    match value {
+                        None => CellValue::None,
+                        Some(value) => CellValue::Float(value)
+                    }
+                }).collect::<Vec<CellValue>>()
+            }
+            BankAngle => {
+                data.iter().map(|d| {
+                    let calc = &d;
+                    let value = calc.bank_angle(task_piece);
+                    match value {
+                        None => CellValue::None,
+                        Some(value) => CellValue::Float(value)
+                    }
+                }).collect::<Vec<CellValue>>()
+            }
+            StartViolations => {
+                data.iter().map(|d| match d.start_violations() {
+                    [] => CellValue::None,
+                    violations => CellValue::String(violations.iter().map(|violation| violation.to_string()).collect::<Vec<String>>().join("; ")),
+                }).collect::<Vec<CellValue>>()
+            }
+            FinishBelowMinimum => {
+                data.iter().map(|d| match d.finish_below_minimum() {
+                    None => CellValue::None,
+                    Some(deficit) => CellValue::Int(deficit),
+                }).collect::<Vec<CellValue>>()
+            }
+            Security => {
+                data.iter().map(|d| match &d.security {
+                    None => CellValue::None,
+                    Some(security) => CellValue::String(security.status().to_string()),
+                }).collect::<Vec<CellValue>>()
+            }
         };
 
         let finishes: Vec<bool> = data.iter().map(|calc| {
@@ -2,6 +2,7 @@ pub mod parser;
 pub mod analysis;
 pub mod web_handling;
 pub mod excel;
+pub mod pipeline;
 
 pub enum PathStrategy {
     Linux,
@@ -0,0 +1,124 @@
+use std::fmt;
+use thiserror::Error;
+use crate::parser::pilot_info::PilotInfo;
+use crate::parser::task::{DefaultZones, Task};
+use crate::parser::util;
+
+/// What is wrong with an IGC or task file
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ParseErrorKind {
+    #[error("the task has no start")]
+    NoStart,
+    #[error("the task has no finish")]
+    NoFinish,
+    #[error("the task has too few turnpoints")]
+    NoTurnpoints,
+    #[error("the task has {points} points but {descriptions} zone descriptions")]
+    NotSameAmountOfDescriptionsAsTurnpoints { points: usize, descriptions: usize },
+    #[error("the file could not be read")]
+    Unreadable,
+    #[error("there is no task in the file")]
+    NoTaskInFile,
+    #[error("the waypoint {0} is not in the file")]
+    UnknownWaypoint(String),
+    #[error("the zone has no style")]
+    MissingStyle,
+    #[error("unknown zone style {0}")]
+    UnknownStyle(u16),
+    #[error("{0} is not a valid number")]
+    InvalidNumber(String),
+    #[error("the header {0} is missing")]
+    MissingHeader(&'static str),
+    #[error("{0} is not a valid time zone")]
+    InvalidTimeZone(String),
+    #[error("there are no valid fixes")]
+    NoFixes,
+    #[error("there is no valid date")]
+    NoDate,
+}
+
+/// A parsing error with the line of the file that caused it, if it is known
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub line: Option<usize>, //counted from 1
+    pub text: Option<String>,
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind) -> Self {
+        Self { kind, line: None, text: None }
+    }
+
+    pub(crate) fn with_text(mut self, text: &str) -> Self {
+        self.text = Some(text.to_string());
+        self
+    }
+
+    /// Finds the line number of the offending text in the contents it was read from
+    pub(crate) fn locate(mut self, contents: &str) -> Self {
+        if let (None, Some(text)) = (self.line, &self.text) {
+            self.line = contents.lines().position(|line| line.trim() == text.trim()).map(|index| index + 1);
+        }
+        self
+    }
+}
+
+impl From<ParseErrorKind> for ParseError {
+    fn from(kind: ParseErrorKind) -> Self {
+        Self::new(kind)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        match (self.line, &self.text) {
+            (Some(line), Some(text)) => write!(f, " (line {}: {})", line, text),
+            (None, Some(text)) => write!(f, " ({})", text),
+            (Some(line), None) => write!(f, " (line {})", line),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Every problem found in the log, this tells why a pilot could not be analysed
+pub fn diagnostics(contents: &str, zones: &DefaultZones) -> Vec<ParseError> {
+    let mut diagnostics = vec![];
+    if let Err(error) = Task::parse_with(contents, zones) { diagnostics.push(error) };
+    if let Err(error) = PilotInfo::parse(contents) { diagnostics.push(error) };
+    if util::get_date(contents).is_err() { diagnostics.push(ParseErrorKind::NoDate.into()) };
+    if util::get_fixes(contents).is_empty() { diagnostics.push(ParseErrorKind::NoFixes.into()) };
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed_log_is_diagnosed_without_panicking() {
+        let contents = util::get_contents("examples/ast.igc").expect("failed to read file");
+        assert!(diagnostics(&contents, &DefaultZones::default()).is_empty());
+
+        let malformed = contents
+            .replace("LSEEYOU OZ=2,Style=1", "LSEEYOU OZ=2,Style=7")
+            .replace("LCU::HPTZNTIMEZONE:2", "LCU::HPTZNTIMEZONE:CET");
+        let problems = diagnostics(&malformed, &DefaultZones::default());
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].kind, ParseErrorKind::UnknownStyle(7));
+        for problem in &problems {
+            let line = problem.line.expect("no line number");
+            assert_eq!(malformed.lines().nth(line - 1).map(str::trim), problem.text.as_deref());
+        }
+        assert_eq!(problems[1].kind, ParseErrorKind::InvalidTimeZone("CET".to_string()));
+
+        let malformed = contents.replace("R1=5000m", "R1=99999999m");
+        let problems = diagnostics(&malformed, &DefaultZones::default());
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].kind, ParseErrorKind::InvalidNumber("99999999".to_string()));
+        assert!(problems[0].line.is_some());
+    }
+}
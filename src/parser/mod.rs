@@ -1,3 +1,6 @@
 pub mod util;
 pub mod task;
-pub mod pilot_info;
\ No newline at end of file
+pub mod task_file;
+pub mod pilot_info;
+pub mod error;
+pub mod security;
\ No newline at end of file
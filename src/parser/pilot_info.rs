@@ -1,43 +1,46 @@
-use regex::Regex;
+use crate::parser::error::{ParseError, ParseErrorKind};
 
 pub struct PilotInfo{
     pub glider_type: String,
     pub comp_id: String,
     pub time_zone: i8,
+    pub pilot: Option<String>,
+    pub registration: Option<String>,
+    pub comp_class: Option<String>,
+    pub logger_type: Option<String>,
+    pub gps: Option<String>,
+    pub firmware: Option<String>,
+    pub hardware: Option<String>,
 }
 
-#[derive(Debug)]
-pub struct PilotInfoParseError;
-
 impl PilotInfo {
-    pub fn parse(contents: &str) -> Result<Self, PilotInfoParseError> {
-        let glider_type = PilotElem::GliderType.get_element(
-            contents.lines().find(|s| s.trim().starts_with("LCU::HPGTYGLIDERTYPE:"))
-        );
-        let comp_id = PilotElem::CompetitionId.get_element(
-            contents.lines().find(|s| s.trim().starts_with("LCU::HPCIDCOMPETITIONID:"))
-        );
-        let time_zone = PilotElem::TimeZone.get_element(
-            contents.lines().find(|s| s.trim().starts_with("LCU::HPTZNTIMEZONE:"))
-        );
-
-        let (glider_type, comp_id, time_zone) = match (glider_type, comp_id, time_zone) {
-            (Some(glider_type), Some(comp_id), Some(time_zone)) =>
-                (
-                    glider_type,
-                    comp_id,
-                    match time_zone.parse::<i8>() {
-                        Ok(time_zone) => time_zone,
-                        Err(_) => return Err(PilotInfoParseError),
-                    }
-                ),
-            _ => return Err(PilotInfoParseError),
+    /// Reads the SeeYou headers of the log and falls back to the standard H records,
+    /// only the competition ID (or the registration) is required
+    pub fn parse(contents: &str) -> Result<Self, ParseError> {
+        let glider_type = PilotElem::GliderType.find(contents).map(|(value, _)| value).unwrap_or_default();
+        let registration = PilotElem::Registration.find(contents).map(|(value, _)| value);
+        let comp_id = PilotElem::CompetitionId.find(contents).map(|(value, _)| value)
+            .or(registration.clone())
+            .ok_or(ParseError::new(ParseErrorKind::MissingHeader("HFCIDCOMPETITIONID")))?;
+        let time_zone = match PilotElem::TimeZone.find(contents) {
+            None => 0, //UTC when the logger does not know
+            Some((time_zone, line)) => match time_zone.parse::<f32>() {
+                Ok(time_zone) if time_zone.abs() <= 14. => time_zone.round() as i8,
+                _ => return Err(ParseError::new(ParseErrorKind::InvalidTimeZone(time_zone)).with_text(line.trim()).locate(contents)),
+            },
         };
 
         Ok(Self {
             glider_type,
             comp_id,
             time_zone,
+            pilot: PilotElem::Pilot.find(contents).map(|(value, _)| value),
+            registration,
+            comp_class: PilotElem::CompetitionClass.find(contents).map(|(value, _)| value),
+            logger_type: PilotElem::LoggerType.find(contents).map(|(value, _)| value),
+            gps: PilotElem::Gps.find(contents).map(|(value, _)| value),
+            firmware: PilotElem::Firmware.find(contents).map(|(value, _)| value),
+            hardware: PilotElem::Hardware.find(contents).map(|(value, _)| value),
         })
     }
 }
@@ -46,21 +49,54 @@ enum PilotElem {
     GliderType,
     CompetitionId,
     TimeZone,
+    Pilot,
+    Registration,
+    CompetitionClass,
+    LoggerType,
+    Gps,
+    Firmware,
+    Hardware,
 }
 
 impl PilotElem {
-    fn get_element(&self, description: Option<&str>) -> Option<String> {
-        let description = description?;
-        let start = match self {
-            PilotElem::GliderType => "LCU::HPGTYGLIDERTYPE:",
-            PilotElem::CompetitionId => "LCU::HPCIDCOMPETITIONID:",
-            PilotElem::TimeZone => "LCU::HPTZNTIMEZONE:",
-        };
+    /// The three letter code of the H record, e.g. `PLT` in `HFPLTPILOTINCHARGE:`
+    fn code(&self) -> &'static str {
+        match self {
+            PilotElem::GliderType => "GTY",
+            PilotElem::CompetitionId => "CID",
+            PilotElem::TimeZone => "TZN",
+            PilotElem::Pilot => "PLT",
+            PilotElem::Registration => "GID",
+            PilotElem::CompetitionClass => "CCL",
+            PilotElem::LoggerType => "FTY",
+            PilotElem::Gps => "GPS",
+            PilotElem::Firmware => "RFW",
+            PilotElem::Hardware => "RHW",
+        }
+    }
 
-        let regex = Regex::new(format!("{start}.+").as_str()).expect("regex failed to compile");
-        let m = regex.find(description.trim())?;
+    /// The value and the line it was found in, the SeeYou `LCU::HP` header is preferred over the standard H record
+    fn find<'a>(&self, contents: &'a str) -> Option<(String, &'a str)> {
+        let seeyou = format!("LCU::HP{}", self.code());
+        let seeyou = contents.lines()
+            .filter(|line| line.trim().starts_with(&seeyou))
+            .find_map(|line| Self::get_element(line.trim().trim_start_matches("LCU::")).map(|value| (value, line)));
+        seeyou.or_else(|| contents.lines()
+            .take_while(|line| !line.starts_with('B')) //the headers are before the first fix
+            .filter(|line| line.starts_with('H') && line.get(2..5) == Some(self.code()))
+            .find_map(|line| Self::get_element(line).map(|value| (value, line))))
+    }
 
-        Some((description.trim()[m.start()+start.len() .. m.end()]).to_string())
+    /// The text after the colon of the header, or after the code if there is no colon
+    fn get_element(header: &str) -> Option<String> {
+        let value = match header.split_once(':') {
+            Some((_, value)) => value,
+            None => header.get(5..)?,
+        }.trim();
+        match value.is_empty() {
+            true => None,
+            false => Some(value.to_string()),
+        }
     }
 }
 
@@ -77,6 +113,25 @@ mod tests {
         assert_eq!(pilot_info.comp_id, "KE");
         assert_eq!(pilot_info.time_zone, 2);
         assert_eq!(pilot_info.glider_type, "LS 8");
+        assert!(pilot_info.pilot.is_some());
+        assert_eq!(pilot_info.comp_class.as_deref(), Some("Standard"));
 
     }
+
+    #[test]
+    fn pilot_info_falls_back_to_h_records() {
+        let contents = util::get_contents("examples/CX.igc").expect("failed to read file");
+        let contents = contents.lines().filter(|line| !line.starts_with("LCU::HP")).collect::<Vec<&str>>().join("\n");
+        let pilot_info = PilotInfo::parse(&contents).expect("failed to parse pilot info");
+        assert_eq!(pilot_info.comp_id, "CX");
+        assert_eq!(pilot_info.time_zone, 3);
+        assert_eq!(pilot_info.glider_type, "ASW 20L");
+        assert_eq!(pilot_info.pilot.as_deref(), Some("Tomas Suchanek"));
+        assert_eq!(pilot_info.registration.as_deref(), Some("OK7779"));
+        assert_eq!(pilot_info.comp_class.as_deref(), Some("Club"));
+        assert_eq!(pilot_info.logger_type.as_deref(), Some("LXNAV,LX9000PF"));
+        assert_eq!(pilot_info.firmware.as_deref(), Some("9.01"));
+        assert_eq!(pilot_info.hardware.as_deref(), Some("57"));
+        assert!(pilot_info.gps.is_some());
+    }
 }
\ No newline at end of file
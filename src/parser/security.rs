@@ -0,0 +1,201 @@
+use std::fmt;
+
+/// IGC approved manufacturers by the three letter code of the A record
+const MANUFACTURERS: [(&str, &str); 25] = [
+    ("ACT", "Aircotec"),
+    ("CAM", "Cambridge Aero Instruments"),
+    ("DSX", "Data Swan"),
+    ("EWA", "EW Avionics"),
+    ("FIL", "Filser"),
+    ("FLA", "Flarm"),
+    ("GCS", "Garrecht"),
+    ("IMI", "IMI Gliding Equipment"),
+    ("LGS", "Logstream"),
+    ("LXN", "LX Navigation"),
+    ("LXV", "LXNAV"),
+    ("NAV", "Naviter"),
+    ("NKL", "Nielsen Kellerman"),
+    ("NTE", "New Technologies"),
+    ("PES", "Peschges"),
+    ("PFE", "PressFinish Electronics"),
+    ("PRT", "Print Technik"),
+    ("SCH", "Scheffel"),
+    ("SDI", "Streamline Data Instruments"),
+    ("TRI", "Triadis Engineering"),
+    ("WES", "Westerboer"),
+    ("XCS", "XCSoar"),
+    ("XLK", "LK8000"),
+    ("XCT", "XCTrack"),
+    ("ZAN", "Zander"),
+];
+
+/// Something in the log that shows it is not as the logger wrote it, lines are counted from 1
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecurityIssue {
+    NoARecord,
+    NoGRecords,
+    MalformedGRecord(usize),
+    RecordAfterGRecords(usize), //only comments (L records) may be added after the security record
+    InvalidLine(usize),
+    InvalidFixLength(usize),
+    MixedLineEndings,
+    LoggerReportedFailure,
+}
+
+impl fmt::Display for SecurityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecurityIssue::NoARecord => write!(f, "the first line is not an A record"),
+            SecurityIssue::NoGRecords => write!(f, "there is no G record"),
+            SecurityIssue::MalformedGRecord(line) => write!(f, "malformed G record at line {}", line),
+            SecurityIssue::RecordAfterGRecords(line) => write!(f, "record added after the G record at line {}", line),
+            SecurityIssue::InvalidLine(line) => write!(f, "invalid record at line {}", line),
+            SecurityIssue::InvalidFixLength(line) => write!(f, "B record does not match the I record at line {}", line),
+            SecurityIssue::MixedLineEndings => write!(f, "some lines have been edited with different line endings"),
+            SecurityIssue::LoggerReportedFailure => write!(f, "the logger reported a security failure"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SecurityStatus {
+    Intact, //the structure is as the logger wrote it, the signature is not checked
+    Unsigned,
+    Suspect,
+}
+
+impl fmt::Display for SecurityStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SecurityStatus::Intact => write!(f, "Structure OK (signature not checked)"),
+            SecurityStatus::Unsigned => write!(f, "Unsigned"),
+            SecurityStatus::Suspect => write!(f, "Suspect"),
+        }
+    }
+}
+
+/// The structural security check of a log.
+/// The G record signature itself can only be checked with the manufacturer's validation program,
+/// this checks that the signature is there, that it is well formed and that nothing was edited around it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verification {
+    pub manufacturer: Option<String>, //the three letter code of the A record
+    pub logger_id: Option<String>,
+    pub issues: Vec<SecurityIssue>,
+}
+
+impl Verification {
+    pub fn verify(contents: &str) -> Self {
+        let mut issues = vec![];
+        let lines = contents.lines().collect::<Vec<&str>>();
+
+        let a_record = lines.first().filter(|line| line.starts_with('A') && line.len() >= 7);
+        if a_record.is_none() { issues.push(SecurityIssue::NoARecord) };
+        let manufacturer = a_record.and_then(|line| line.get(1..4)).map(str::to_string);
+        let logger_id = a_record.and_then(|line| line.get(4..7)).map(str::to_string);
+
+        let has_crlf = contents.contains("\r\n");
+        let has_lf = contents.split("\r\n").any(|part| part.contains('\n'));
+        if has_crlf && has_lf { issues.push(SecurityIssue::MixedLineEndings) };
+
+        let fix_length = lines.iter().find(|line| line.starts_with('I')).and_then(|line| fix_length(line));
+        let first_g = lines.iter().position(|line| line.starts_with('G'));
+        let last_g = lines.iter().rposition(|line| line.starts_with('G'));
+        for (index, line) in lines.iter().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim_end();
+            let signed = match first_g {
+                Some(first_g) => index < first_g,
+                None => true,
+            };
+            let in_signature = first_g.zip(last_g).is_some_and(|(first_g, last_g)| (first_g..=last_g).contains(&index));
+            if !line.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) || (line.is_empty() && signed) {
+                issues.push(SecurityIssue::InvalidLine(line_number));
+            } else if in_signature {
+                if !line.starts_with('G') || line.len() < 2 || !line[1..].chars().all(|c| c.is_ascii_alphanumeric()) {
+                    issues.push(SecurityIssue::MalformedGRecord(line_number));
+                }
+            } else if !signed {
+                if !line.is_empty() && !line.starts_with('L') { issues.push(SecurityIssue::RecordAfterGRecords(line_number)) };
+            } else if !line.starts_with(|c: char| ('A'..='M').contains(&c)) {
+                issues.push(SecurityIssue::InvalidLine(line_number));
+            } else if line.starts_with('B') && fix_length.is_some_and(|length| line.len() != length) {
+                issues.push(SecurityIssue::InvalidFixLength(line_number));
+            }
+        }
+
+        if first_g.is_none() { issues.push(SecurityIssue::NoGRecords) };
+
+        let security_header = lines.iter().find(|line| line.starts_with("HFFRS"));
+        if security_header.is_some_and(|header| !header.to_uppercase().contains("OK")) {
+            issues.push(SecurityIssue::LoggerReportedFailure);
+        }
+
+        issues.dedup();
+        Self { manufacturer, logger_id, issues }
+    }
+
+    pub fn status(&self) -> SecurityStatus {
+        match self.issues.as_slice() {
+            [] => SecurityStatus::Intact,
+            [SecurityIssue::NoGRecords] => SecurityStatus::Unsigned,
+            _ => SecurityStatus::Suspect,
+        }
+    }
+
+    /// The name of the logger manufacturer, or the code of the A record if it is unknown
+    pub fn logger(&self) -> Option<String> {
+        let code = self.manufacturer.as_ref()?;
+        Some(MANUFACTURERS.iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(code))
+            .map_or(code.clone(), |(_, name)| name.to_string()))
+    }
+}
+
+/// The length of a B record given by the extensions of the I record, the last extension ends at the last byte
+fn fix_length(i_record: &str) -> Option<usize> {
+    let count = i_record.get(1..3)?.parse::<usize>().ok()?;
+    if count == 0 { return Some(35) };
+    let last = 3 + (count - 1) * 7;
+    i_record.get(last + 2..last + 4)?.parse::<usize>().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::util;
+    use super::*;
+
+    #[test]
+    fn untouched_log_is_valid() {
+        let contents = util::get_contents("examples/ast.igc").expect("failed to read file");
+        let verification = Verification::verify(&contents);
+        assert_eq!(verification.issues, vec![]);
+        assert_eq!(verification.status(), SecurityStatus::Intact);
+        assert_eq!(verification.logger().as_deref(), Some("LXNAV"));
+        assert_eq!(verification.logger_id.as_deref(), Some("9BA"));
+    }
+
+    #[test]
+    fn edited_logs_are_flagged() {
+        let contents = util::get_contents("examples/ast.igc").expect("failed to read file");
+        let lines = contents.lines().collect::<Vec<&str>>();
+        let fix = lines.iter().position(|line| line.starts_with('B')).expect("no fixes");
+
+        let unsigned = lines.iter().filter(|line| !line.starts_with('G')).copied().collect::<Vec<&str>>().join("\n");
+        assert_eq!(Verification::verify(&unsigned).status(), SecurityStatus::Unsigned);
+
+        let mut shortened = lines.clone();
+        let short_fix = &lines[fix][..35];
+        shortened[fix] = short_fix;
+        let verification = Verification::verify(&shortened.join("\n"));
+        assert_eq!(verification.status(), SecurityStatus::Suspect);
+        assert!(verification.issues.contains(&SecurityIssue::InvalidFixLength(fix + 1)));
+
+        let appended = format!("{}\n{}\n", contents, lines[fix]);
+        let verification = Verification::verify(&appended);
+        assert!(verification.issues.iter().any(|issue| matches!(issue, SecurityIssue::RecordAfterGRecords(_))));
+
+        let mixed = contents.replacen('\n', "\r\n", 1);
+        assert!(Verification::verify(&mixed).issues.contains(&SecurityIssue::MixedLineEndings));
+    }
+}
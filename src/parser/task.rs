@@ -1,33 +1,43 @@
 use igc_parser::records::util::Time;
 use regex::Regex;
+use crate::parser::error::{ParseError, ParseErrorKind};
 use crate::parser::util;
 use crate::parser::util::TurnpointRecord;
 
+type FloatMeters = f32;
+type Meters = i16;
+type Kph = f32;
+
+const TASK_TOLERANCE: FloatMeters = 200.; //how far apart the same point may be in two declarations
+
 enum DescriptionElem {
-    R1, R2, A1, A2, Style, AAT,
+    R1, R2, A1, A2, A12, Style, AAT, Line, Reduce,
 }
 
 impl DescriptionElem {
-    fn get_element(&self, description: &str) -> Option<u16> {
+    fn get_element(&self, description: &str) -> Result<Option<u16>, ParseError> {
         let (start, end) = match self {
             DescriptionElem::R1 => ("R1=", "m,"),
             DescriptionElem::R2 => ("R2=", "m"),
             DescriptionElem::A1 => ("A1=", ","),
-            DescriptionElem::A2 => ("A2=", ","),
+            DescriptionElem::A2 => ("A2=", ""),
+            DescriptionElem::A12 => ("A12=", ""),
             DescriptionElem::Style => (",Style=", ","),
             DescriptionElem::AAT => ("AAT=", ""),
+            DescriptionElem::Line => ("Line=", ""),
+            DescriptionElem::Reduce => ("Reduce=", ""),
         };
 
         let regex = Regex::new(format!("{start}[0-9]+{end}").as_str()).expect("regex failed to compile");
         let re_match = regex.find(description);
-        re_match.map(|m| match description[m.start()+start.len() .. m.end()-end.len()].parse().ok() {
-            Some(num) => num,
-            None => panic!("failed to parse number from {description}"),
-        })
+        re_match.map(|m| {
+            let number = &description[m.start()+start.len() .. m.end()-end.len()];
+            number.parse().map_err(|_| ParseError::new(ParseErrorKind::InvalidNumber(number.to_string())).with_text(description))
+        }).transpose()
     }
 }
 
-
+#[derive(Clone)]
 pub enum TaskComponent {
     Tp(Turnpoint),
     Start(Turnpoint),
@@ -35,15 +45,21 @@ pub enum TaskComponent {
 }
 
 impl TaskComponent {
-    fn parse(description: &str, loc: TurnpointRecord) -> Self {
-        let style = DescriptionElem::Style.get_element(description);
-        let tp = Turnpoint::parse(description, loc);
-        match style {
-            Some(2) => Self::Start(tp),
-            Some(1) => Self::Tp(tp),
-            Some(3) => Self::Finish(tp),
-            None => panic!("style param not found in {description}"),
-            _ => panic!("style was parsed to unknown number not in (1,2,3), {description}"),
+    /// Parses the point at `index` of a task of `count` points.
+    /// The first point, or a zone with `OZ=-1`, is the start and the last point is the finish,
+    /// the `Style` of the zone is only its orientation
+    pub(crate) fn parse(description: &str, loc: TurnpointRecord, index: usize, count: usize) -> Result<Self, ParseError> {
+        match DescriptionElem::Style.get_element(description)? {
+            Some(0..=4) => {},
+            None => return Err(ParseError::new(ParseErrorKind::MissingStyle).with_text(description)),
+            Some(style) => return Err(ParseError::new(ParseErrorKind::UnknownStyle(style)).with_text(description)),
+        }
+        let tp = Turnpoint::parse(description, loc)?;
+        let is_start = index == 0 || description.contains("OZ=-1,");
+        match (is_start, index + 1 == count) {
+            (true, _) => Ok(Self::Start(tp)),
+            (false, true) => Ok(Self::Finish(tp)),
+            (false, false) => Ok(Self::Tp(tp)),
         }
     }
 
@@ -63,8 +79,43 @@ impl TaskComponent {
             TaskComponent::Finish(inner) => inner,
         }
     }
+
+    fn inner_mut(&mut self) -> &mut Turnpoint {
+        match self {
+            TaskComponent::Tp(inner) => inner,
+            TaskComponent::Start(inner) => inner,
+            TaskComponent::Finish(inner) => inner,
+        }
+    }
 }
 
+/// How the observation zone is rotated, this is the `Style` param of the SeeYou description
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZoneOrientation {
+    Fixed,
+    Symmetrical,
+    ToNext,
+    ToPrevious,
+    ToStart,
+}
+
+impl ZoneOrientation {
+    fn from_style(style: Option<u16>) -> Self {
+        match style {
+            Some(0) => Self::Fixed,
+            Some(2) => Self::ToNext,
+            Some(3) => Self::ToPrevious,
+            Some(4) => Self::ToStart,
+            _ => Self::Symmetrical,
+        }
+    }
+}
+
+/// A point of the task and its observation zone.
+/// `r1`/`a1` is the outer sector (`a1` is half the opening angle, 180 is a cylinder),
+/// `r2`/`a2` is the inner sector of a keyhole, if `a2` is 0 then `r2` is an inner radius that is cut out of the zone.
+/// A `line` is perpendicular to the leg and has a length of `2 * r1`.
+#[derive(Clone)]
 pub struct Turnpoint {
     pub latitude: f32,
     pub longitude: f32,
@@ -73,17 +124,26 @@ pub struct Turnpoint {
     pub a1: u16,
     pub r2: u16,
     pub a2: u16,
+    pub a12: u16,
+    pub orientation: ZoneOrientation,
+    pub line: bool,
+    pub reduce: bool, //the task distance is measured to the edge of a finish ring instead of its centre
+    pub(crate) direction: f32, //bearing of the zone bisector pointing away from the course, set when the task is parsed
     aat: bool,
 }
 
 impl Turnpoint {
-    pub(crate) fn parse(description: &str, loc: TurnpointRecord) -> Self {
-        let r1 = DescriptionElem::R1.get_element(description).unwrap_or(0);
-        let a1 = DescriptionElem::A1.get_element(description).unwrap_or(0);
-        let r2 = DescriptionElem::R2.get_element(description).unwrap_or(0);
-        let a2 = DescriptionElem::A2.get_element(description).unwrap_or(0);
-        let aat = DescriptionElem::AAT.get_element(description).is_some();
-        Self {
+    pub(crate) fn parse(description: &str, loc: TurnpointRecord) -> Result<Self, ParseError> {
+        let r1 = DescriptionElem::R1.get_element(description)?.unwrap_or(0);
+        let a1 = DescriptionElem::A1.get_element(description)?.unwrap_or(0);
+        let r2 = DescriptionElem::R2.get_element(description)?.unwrap_or(0);
+        let a2 = DescriptionElem::A2.get_element(description)?.unwrap_or(0);
+        let a12 = DescriptionElem::A12.get_element(description)?.unwrap_or(0);
+        let orientation = ZoneOrientation::from_style(DescriptionElem::Style.get_element(description)?);
+        let line = DescriptionElem::Line.get_element(description)?.unwrap_or(0) == 1;
+        let reduce = DescriptionElem::Reduce.get_element(description)?.unwrap_or(0) == 1;
+        let aat = DescriptionElem::AAT.get_element(description)?.is_some();
+        Ok(Self {
             latitude: loc.latitude,
             longitude: loc.longitude,
             name: loc.name,
@@ -91,66 +151,256 @@ impl Turnpoint {
             a1,
             r2,
             a2,
+            a12,
+            orientation,
+            line,
+            reduce,
+            direction: a12 as f32,
             aat,
-        }
+        })
     }
 }
 
-
+#[derive(Clone)]
 pub struct Task {
     pub points: Vec<TaskComponent>,
     pub task_type: TaskType,
+    pub start_rules: StartRules,
+    pub finish_rules: FinishRules,
+    pub default_zones: bool, //the zones were not declared, they come from `DefaultZones`
 }
 
+#[derive(Clone)]
 pub enum TaskType {
     AAT(Time),
     AST,
 }
 
-#[derive(Debug)]
-pub enum TaskError {
-    NoStart,
-    NoFinish,
-    NoTurnpoints,
-    NotSameAmountOfDescriptionsAsTurnpoints,
+/// What the maximum start altitude is measured from
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum AltitudeReference {
+    #[default]
+    Msl,
+    Agl, //above the takeoff
+}
+
+/// The start rules of the competition, the limits that are not set are not checked
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StartRules {
+    pub gate_open: Option<Time>, //local time
+    pub max_altitude: Option<Meters>,
+    pub altitude_reference: AltitudeReference,
+    pub max_ground_speed: Option<Kph>,
+    pub pre_start_altitude: Option<Meters>, //the pilot must be below this after the gate opens and before starting
+}
+
+impl AltitudeReference {
+    /// Reads the reference from a task option, `AGL` or `MSL` which is also used when it is missing
+    fn from_option(options: &str, key: &str) -> Self {
+        match option_value(options, key).is_some_and(|value| value.eq_ignore_ascii_case("AGL")) {
+            true => AltitudeReference::Agl,
+            false => AltitudeReference::Msl,
+        }
+    }
+}
+
+impl StartRules {
+    /// Reads the rules from SeeYou task options, `NoStart=HH:MM:SS` is the opening of the start gate.
+    /// SeeYou does not store the start limits, they are read from
+    /// `MaxStartAlt=1500m`, `StartAltRef=AGL`, `MaxStartSpeed=150kmh` and `PreStartAlt=1200m`
+    pub(crate) fn from_seeyou_options(options: &str) -> Self {
+        Self {
+            gate_open: util::parse_option_time(options, "NoStart"),
+            max_altitude: option_value(options, "MaxStartAlt").and_then(|value| parse_altitude(&value)),
+            altitude_reference: AltitudeReference::from_option(options, "StartAltRef"),
+            max_ground_speed: option_value(options, "MaxStartSpeed").and_then(|value| parse_speed(&value)),
+            pre_start_altitude: option_value(options, "PreStartAlt").and_then(|value| parse_altitude(&value)),
+        }
+    }
+
+    /// SeeYou stores the maximum start altitude as `MaxAlt` of the start zone, 0 is no limit.
+    /// The limit of the task options is used over the zone's
+    fn with_zone(mut self, description: &str) -> Self {
+        let regex = Regex::new("MaxAlt=([0-9.]+)m").expect("regex failed to compile");
+        let zone_limit = regex.captures(description)
+            .and_then(|captures| captures[1].parse::<f32>().ok())
+            .filter(|altitude| *altitude > 0.)
+            .map(|altitude| altitude as Meters);
+        self.max_altitude = self.max_altitude.or(zone_limit);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The finish rules of the competition, a finish below the minimum altitude is reported for the scorer to penalise
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FinishRules {
+    pub min_altitude: Option<Meters>,
+    pub altitude_reference: AltitudeReference,
+}
+
+impl FinishRules {
+    /// SeeYou does not store the finish limit, it is read from the task options `MinFinishAlt=500m` and `FinishAltRef=AGL`
+    pub(crate) fn from_seeyou_options(options: &str) -> Self {
+        Self {
+            min_altitude: option_value(options, "MinFinishAlt").and_then(|value| parse_altitude(&value)),
+            altitude_reference: AltitudeReference::from_option(options, "FinishAltRef"),
+        }
+    }
+}
+
+/// The value of a `key=value` param of the task options, the key is matched without case
+fn option_value(options: &str, key: &str) -> Option<String> {
+    options.split(',')
+        .filter_map(|param| param.trim().split_once('='))
+        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+        .map(|(_, value)| value.trim().to_string())
+}
+
+/// Splits a value like `1500m` into the number and the unit
+fn number_and_unit(value: &str) -> Option<(f32, String)> {
+    let split = value.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-')).unwrap_or(value.len());
+    let number = value[..split].parse::<f32>().ok()?;
+    Some((number, value[split..].trim().to_lowercase()))
+}
+
+fn parse_altitude(value: &str) -> Option<Meters> {
+    let (number, unit) = number_and_unit(value)?;
+    match unit.as_str() {
+        "" | "m" => Some(number as Meters),
+        "ft" => Some((number * 0.3048) as Meters),
+        _ => None,
+    }
+}
+
+fn parse_speed(value: &str) -> Option<Kph> {
+    let (number, unit) = number_and_unit(value)?;
+    match unit.as_str() {
+        "" | "kmh" | "km/h" => Some(number),
+        "kt" | "kts" => Some(number * 1.852),
+        "ms" | "m/s" => Some(number * 3.6),
+        _ => None,
+    }
+}
+
+/// Observation zones of a declaration that has no SeeYou descriptions, the radius of a line is half its length
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefaultZones {
+    pub start_line: bool,
+    pub start_radius: u16,
+    pub turnpoint_radius: u16,
+    pub finish_line: bool,
+    pub finish_radius: u16,
+}
+
+impl Default for DefaultZones {
+    fn default() -> Self {
+        Self {
+            start_line: true,
+            start_radius: 5000,
+            turnpoint_radius: 500,
+            finish_line: false,
+            finish_radius: 3000,
+        }
+    }
+}
+
+impl DefaultZones {
+    /// The SeeYou description of the zone of the point at `index` in a task of `count` points
+    fn description(&self, index: usize, count: usize) -> String {
+        let (style, radius, line) = match index {
+            0 => (2, self.start_radius, self.start_line),
+            i if i + 1 == count => (3, self.finish_radius, self.finish_line),
+            _ => (1, self.turnpoint_radius, false),
+        };
+        let line = match line {
+            true => ",Line=1",
+            false => "",
+        };
+        let reduce = match style {
+            3 => ",Reduce=1", //the finish ring is measured to its edge
+            _ => "",
+        };
+        format!("LSEEYOU OZ={},Style={},R1={}m,A1=180,R2=0m,A2=0{}{}", index as i32 - 1, style, radius, line, reduce)
+    }
 }
 
 impl Task {
-    pub fn parse(contents: &str) -> Result<Self, TaskError> {
+    pub fn parse(contents: &str) -> Result<Self, ParseError> {
+        Self::parse_with(contents, &DefaultZones::default())
+    }
+
+    /// Parses the SeeYou task of the log, or the standard C record declaration if there is none.
+    /// A declaration without observation zone descriptions is given the default zones
+    pub fn parse_with(contents: &str, zones: &DefaultZones) -> Result<Self, ParseError> {
         //the contents should be split into parts so there is not many unnecessary run through O(3n) -> O(n)
         let tps = util::get_turnpoint_locations(contents);
         let descriptions = util::get_turnpoint_descriptions(contents);
         let task_time = util::get_task_time(contents);
-        if tps.len() != descriptions.len() { return Err(TaskError::NotSameAmountOfDescriptionsAsTurnpoints) };
-        let points = tps.into_iter().zip(descriptions).map(|(tpl, desc)| {
-            TaskComponent::parse(&desc, tpl)
-        }).collect::<Vec<TaskComponent>>();
+        let options = util::get_task_options(contents).unwrap_or_default();
+        let (start_rules, finish_rules) = (StartRules::from_seeyou_options(&options), FinishRules::from_seeyou_options(&options));
+        let (tps, descriptions, default_zones) = match tps.is_empty() {
+            false => (tps, descriptions, false),
+            true => {
+                let tps = util::get_declared_turnpoints(contents);
+                match descriptions.len() == tps.len() && !tps.is_empty() {
+                    true => (tps, descriptions, false),
+                    false => {
+                        let descriptions = (0..tps.len()).map(|index| zones.description(index, tps.len())).collect();
+                        (tps, descriptions, true)
+                    }
+                }
+            }
+        };
+        let task = Self::from_descriptions(tps, descriptions, task_time, start_rules, finish_rules).map_err(|error| error.locate(contents))?;
+        Ok(Self { default_zones, ..task })
+    }
+
+    /// Builds the task from the points and their SeeYou observation zone descriptions, in task order
+    pub(crate) fn from_descriptions(tps: Vec<TurnpointRecord>, descriptions: Vec<String>, task_time: Option<Time>, start_rules: StartRules, finish_rules: FinishRules) -> Result<Self, ParseError> {
+        if tps.len() != descriptions.len() {
+            return Err(ParseErrorKind::NotSameAmountOfDescriptionsAsTurnpoints { points: tps.len(), descriptions: descriptions.len() }.into())
+        };
+        let start_rules = match descriptions.first() {
+            Some(start) => start_rules.with_zone(start),
+            None => start_rules,
+        };
+        let count = tps.len();
+        let points = tps.into_iter().zip(descriptions).enumerate().map(|(index, (tpl, desc))| {
+            TaskComponent::parse(&desc, tpl, index, count)
+        }).collect::<Result<Vec<TaskComponent>, ParseError>>()?;
 
-        if points.len() < 3 { return Err(TaskError::NoTurnpoints) };
+        if points.len() < 3 { return Err(ParseErrorKind::NoTurnpoints.into()) };
 
         match points.first() { //Checks if there is a turnpoint and if the first one is start
             Some(p) => match p {
                 TaskComponent::Start(_) => {},
-                _ => return Err(TaskError::NoStart)
+                _ => return Err(ParseErrorKind::NoStart.into())
             },
-            None => return Err(TaskError::NoTurnpoints),
+            None => return Err(ParseErrorKind::NoTurnpoints.into()),
         }
 
         match points.last() { //checks if the last one is a finish
             Some(p) => match p {
                 TaskComponent::Finish(_) => {},
-                _ => return Err(TaskError::NoFinish)
+                _ => return Err(ParseErrorKind::NoFinish.into())
             },
-            None => return Err(TaskError::NoTurnpoints),
+            None => return Err(ParseErrorKind::NoTurnpoints.into()),
         }
 
-        for i in &points[1..points.len()-2] { //checks if all points except first and last, are turnpoints
+        for i in &points[1..points.len()-1] { //checks if all points except first and last, are turnpoints
             match i {
                 TaskComponent::Tp(_) => {}
-                _ => return Err(TaskError::NoTurnpoints),
+                _ => return Err(ParseErrorKind::NoTurnpoints.into()),
             }
         }
 
+        let mut points = points;
+        orient_zones(&mut points);
+
         let task_type = match points[1].is_aat() {
             true => {
                 match task_time {
@@ -166,9 +416,41 @@ impl Task {
             Self {
                 points,
                 task_type,
+                start_rules,
+                finish_rules,
+                default_zones: false,
             }
         )
     }
+
+    /// Checks if the other task is of the same type and has the same points and zones as this one, within `TASK_TOLERANCE`.
+    /// The zones are not compared when either task was given the default zones of a plain declaration
+    pub fn matches(&self, other: &Task) -> bool {
+        let same_type = match (&self.task_type, &other.task_type) {
+            (TaskType::AAT(time), TaskType::AAT(other_time)) => time == other_time,
+            (TaskType::AST, TaskType::AST) => true,
+            _ => false,
+        };
+        let compare_zones = !self.default_zones && !other.default_zones;
+        same_type && self.points.len() == other.points.len() && self.points.iter().zip(&other.points).all(|(point, other)| {
+            let (point, other) = (point.inner(), other.inner());
+            let same_zone = !compare_zones || (point.r1 == other.r1 && point.line == other.line);
+            point.distance_to(other) <= TASK_TOLERANCE && same_zone
+        })
+    }
+}
+
+/// Rotates every observation zone according to its orientation and the neighbouring points
+fn orient_zones(points: &mut Vec<TaskComponent>) {
+    let locations = points.iter()
+        .map(|p| (p.inner().latitude, p.inner().longitude))
+        .collect::<Vec<(f32, f32)>>();
+    let start = locations[0];
+    for (i, point) in points.iter_mut().enumerate() {
+        let prev = if i == 0 { None } else { locations.get(i - 1).copied() };
+        let next = locations.get(i + 1).copied();
+        point.inner_mut().orient(prev, next, start);
+    }
 }
 
 #[cfg(test)]
@@ -183,7 +465,7 @@ mod tests {
         let mut turnpoint = get_turnpoint_locations("LCU::C5624583N00924583E0005ViborgFlp");
         if let TaskComponent::Tp(comp) = TaskComponent::parse(
             "LSEEYOU OZ=2,Style=1,SpeedStyle=1,R1=500m,A1=180,R2=0m,A2=0,MaxAlt=0.0m",
-            turnpoint.remove(0)) {
+            turnpoint.remove(0), 3, 7).expect("failed to parse turnpoint") {
             assert_eq!(comp.r1, 500);
             assert_eq!(comp.a1, 180);
             assert_eq!(comp.r2, 0);
@@ -198,11 +480,13 @@ mod tests {
         let mut turnpoint = get_turnpoint_locations("LCU::C5600500N00906683E0047FasterholtBanX");
         if let TaskComponent::Start(comp) = TaskComponent::parse(
             "LSEEYOU OZ=-1,Style=2,SpeedStyle=0,R1=5000m,A1=180,R2=0m,A2=0,MaxAlt=0.0m,Line=1",
-            turnpoint.remove(0)) {
+            turnpoint.remove(0), 0, 7).expect("failed to parse turnpoint") {
             assert_eq!(comp.r1, 5000);
             assert_eq!(comp.a1, 180);
             assert_eq!(comp.r2, 0);
             assert_eq!(comp.a2, 0);
+            assert!(comp.line);
+            assert_eq!(comp.orientation, ZoneOrientation::ToNext);
         } else {
             assert!(false);
         };
@@ -213,7 +497,7 @@ mod tests {
         let mut turnpoint = get_turnpoint_locations("LCU::C5600633N00900867E0851ArnborgFlp");
         if let TaskComponent::Finish(comp) = TaskComponent::parse(
             "LSEEYOU OZ=5,Style=3,SpeedStyle=2,R1=3000m,A1=180,R2=0m,A2=0,MaxAlt=0.0m,Reduce=1",
-            turnpoint.remove(0)) {
+            turnpoint.remove(0), 6, 7).expect("failed to parse turnpoint") {
             assert_eq!(comp.r1, 3000);
             assert_eq!(comp.a1, 180);
             assert_eq!(comp.r2, 0);
@@ -223,6 +507,24 @@ mod tests {
         };
     }
 
+    #[test]
+    fn turnpoints_oriented_to_the_next_or_previous_point_stay_turnpoints() {
+        let contents = util::get_contents("examples/ast.igc").expect("Failed to get contents")
+            .replace("LSEEYOU OZ=1,Style=1,", "LSEEYOU OZ=1,Style=2,")
+            .replace("LSEEYOU OZ=2,Style=1,", "LSEEYOU OZ=2,Style=3,");
+        let task = Task::parse(&contents).expect("Failed to parse task");
+        assert_eq!(task.points.len(), 7);
+        match (&task.points[2], &task.points[3]) {
+            (TaskComponent::Tp(to_next), TaskComponent::Tp(to_previous)) => {
+                assert_eq!(to_next.orientation, ZoneOrientation::ToNext);
+                assert_eq!(to_previous.orientation, ZoneOrientation::ToPrevious);
+            }
+            _ => panic!("the points were not parsed as turnpoints"),
+        }
+        assert!(matches!(task.points.first(), Some(TaskComponent::Start(_))));
+        assert!(matches!(task.points.last(), Some(TaskComponent::Finish(_))));
+    }
+
     #[test]
     fn ast_task_type_and_start_is_parsed_correctly() {
         let contents = util::get_contents("examples/ast.igc").expect("Failed to get contents");
@@ -258,4 +560,45 @@ mod tests {
             assert!(false)
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn plain_declaration_is_given_default_zones() {
+        let contents = util::get_contents("examples/ast.igc").expect("Failed to get contents");
+        let seeyou = Task::parse(&contents).expect("Failed to parse task");
+        let plain = contents.lines()
+            .filter(|line| !line.starts_with("LCU::") && !line.starts_with("LSEEYOU"))
+            .collect::<Vec<&str>>()
+            .join("\n");
+        let zones = DefaultZones { turnpoint_radius: 1000, ..DefaultZones::default() };
+        let task = Task::parse_with(&plain, &zones).expect("Failed to parse task");
+        assert!(task.matches(&seeyou));
+        assert!(matches!(task.task_type, TaskType::AST));
+        match (task.points.first(), task.points.last()) {
+            (Some(TaskComponent::Start(start)), Some(TaskComponent::Finish(finish))) => {
+                assert!(start.line);
+                assert_eq!(start.r1, 5000);
+                assert_eq!(finish.r1, 3000);
+            }
+            _ => assert!(false),
+        }
+        assert_eq!(task.points[1].inner().r1, 1000);
+        assert_eq!(task.points[1].inner().a1, 180);
+        assert!(task.default_zones && !seeyou.default_zones);
+    }
+
+    #[test]
+    fn declarations_with_other_zones_or_task_time_do_not_match() {
+        let ast = util::get_contents("examples/ast.igc").expect("Failed to get contents");
+        let seeyou = Task::parse(&ast).expect("Failed to parse task");
+        let wider = Task::parse(&ast.replace("LSEEYOU OZ=1,Style=1,SpeedStyle=1,R1=500m", "LSEEYOU OZ=1,Style=1,SpeedStyle=1,R1=1000m")).expect("Failed to parse task");
+        assert!(seeyou.matches(&seeyou.clone()));
+        assert!(!seeyou.matches(&wider));
+
+        let aat = util::get_contents("examples/aat.igc").expect("Failed to get contents");
+        let area = Task::parse(&aat).expect("Failed to parse task");
+        let longer = Task::parse(&aat.replace("TaskTime=02:00:00", "TaskTime=02:30:00")).expect("Failed to parse task");
+        assert!(!area.matches(&longer));
+        let assigned = Task::parse(&aat.replace(",AAT=1", "")).expect("Failed to parse task");
+        assert!(!area.matches(&assigned));
+    }
+}
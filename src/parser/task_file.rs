@@ -0,0 +1,300 @@
+use igc_parser::records::util::Time;
+use regex::Regex;
+use crate::parser::error::{ParseError, ParseErrorKind};
+use crate::parser::task::{AltitudeReference, FinishRules, StartRules, Task};
+use crate::parser::util;
+use crate::parser::util::TurnpointRecord;
+
+type Meters = u32;
+
+const CUP_TASK_MARKER: &str = "-----Related Tasks-----";
+const XCSOAR_SECTOR_RADIUS: Meters = 10000; //XCSoar does not store the radius of FAI sectors and keyholes
+
+impl Task {
+    /// Reads the task from a SeeYou `.cup` (or `.tsk`) file or an XCSoar `.tsk` file
+    pub fn from_file(path: &str) -> Result<Self, ParseError> {
+        let contents = util::get_contents(path).map_err(|_| ParseError::new(ParseErrorKind::Unreadable))?;
+        match contents.contains("<Task") {
+            true => Self::from_xcsoar(&contents),
+            false => Self::from_cup(&contents),
+        }
+    }
+
+    /// Reads the first task of the task section of a SeeYou `.cup` file,
+    /// the points are looked up by name in the waypoints above the task section
+    pub fn from_cup(contents: &str) -> Result<Self, ParseError> {
+        let (waypoints, tasks) = contents.split_once(CUP_TASK_MARKER).ok_or(ParseError::new(ParseErrorKind::NoTaskInFile))?;
+        let waypoints = waypoints.lines()
+            .map(split_cup_line)
+            .filter_map(|fields| {
+                let latitude = cup_coordinate(fields.get(3)?, 2)?;
+                let longitude = cup_coordinate(fields.get(4)?, 3)?;
+                Some(TurnpointRecord { latitude, longitude, name: fields.first().cloned() })
+            })
+            .collect::<Vec<TurnpointRecord>>();
+
+        let mut lines = tasks.lines().map(str::trim).skip_while(|line| !line.starts_with('"'));
+        let names = split_cup_line(lines.next().ok_or(ParseError::new(ParseErrorKind::NoTaskInFile))?);
+        if names.len() < 3 { return Err(ParseErrorKind::NoTurnpoints.into()) };
+        let names = &names[2..names.len() - 1]; //the task name, the takeoff and the landing are not part of the task
+
+        let mut descriptions = vec![None; names.len()];
+        let mut task_time = None;
+        let mut start_rules = StartRules::default();
+        let mut finish_rules = FinishRules::default();
+        for line in lines.take_while(|line| !line.starts_with('"')) {
+            if line.starts_with("Options") {
+                task_time = util::parse_task_time(line);
+                start_rules = StartRules::from_seeyou_options(line);
+                finish_rules = FinishRules::from_seeyou_options(line);
+            } else if let Some(zone) = line.strip_prefix("ObsZone=") {
+                let index = zone.split(',').next().and_then(|index| index.parse::<usize>().ok());
+                if let Some(description) = index.and_then(|index| descriptions.get_mut(index)) {
+                    *description = Some(format!("LSEEYOU OZ={},", zone)); //the params are matched up to the next comma
+                }
+            }
+        }
+        let descriptions = descriptions.into_iter().flatten().collect::<Vec<String>>(); //missing zones are reported when the task is built
+
+        let tps = names.iter().map(|name| {
+            waypoints.iter()
+                .find(|waypoint| waypoint.name.as_ref() == Some(name))
+                .map(|waypoint| TurnpointRecord { latitude: waypoint.latitude, longitude: waypoint.longitude, name: waypoint.name.clone() })
+                .ok_or(ParseError::new(ParseErrorKind::UnknownWaypoint(name.clone())))
+        }).collect::<Result<Vec<TurnpointRecord>, ParseError>>()?;
+        Self::from_descriptions(tps, descriptions, task_time, start_rules, finish_rules)
+    }
+
+    /// Reads the task of an XCSoar `.tsk` file, the observation zones are converted to their SeeYou equivalents
+    pub fn from_xcsoar(contents: &str) -> Result<Self, ParseError> {
+        let task_regex = Regex::new("<Task[^>]*>").expect("regex failed to compile");
+        let task_tag = task_regex.find(contents).ok_or(ParseError::new(ParseErrorKind::NoTaskInFile))?.as_str();
+        let task_time = match attribute(task_tag, "type").as_deref() {
+            Some("AAT") => attribute(task_tag, "aat_min_time")
+                .and_then(|seconds| seconds.parse::<u32>().ok())
+                .and_then(|seconds| Time::from_hms((seconds / 3600) as u8, (seconds / 60 % 60) as u8, (seconds % 60) as u8).ok()),
+            _ => None,
+        };
+
+        let point_regex = Regex::new(r#"(?s)<Point[^>]*type="(\w+)"[^>]*>(.*?)</Point>"#).expect("regex failed to compile");
+        let points = point_regex.captures_iter(contents)
+            .map(|captures| (captures[1].to_string(), captures[2].to_string()))
+            .filter(|(point_type, _)| point_type != "OptionalStart")
+            .collect::<Vec<(String, String)>>();
+
+        let mut tps = vec![];
+        let mut descriptions = vec![];
+        for (index, (point_type, point)) in points.iter().enumerate() {
+            let location = tag(point, "Location").ok_or(ParseError::new(ParseErrorKind::NoTurnpoints))?;
+            let coordinate = |name: &str| attribute(location, name).and_then(|value| value.parse::<f32>().ok());
+            let (latitude, longitude) = coordinate("latitude").zip(coordinate("longitude")).ok_or(ParseError::new(ParseErrorKind::NoTurnpoints))?;
+            let name = tag(point, "Waypoint").and_then(|waypoint| attribute(waypoint, "name"));
+            tps.push(TurnpointRecord { latitude, longitude, name });
+
+            let zone = tag(point, "ObservationZone").unwrap_or("");
+            let fixed = matches!(attribute(zone, "type").as_deref(), Some("Sector") | Some("AnnularSector"));
+            let style = match (point_type.as_str(), fixed) {
+                ("Start", _) => 2,
+                ("Finish", _) => 3,
+                (_, true) => 0,
+                (_, false) => 1,
+            };
+            let extra = match point_type.as_str() {
+                "Area" => ",AAT=1",
+                "Finish" => ",Reduce=1", //XCSoar measures the task to the edge of a finish cylinder
+                _ => "",
+            };
+            descriptions.push(format!("LSEEYOU OZ={},Style={},{}{},", index, style, xcsoar_zone(zone), extra));
+        }
+        Self::from_descriptions(tps, descriptions, task_time, xcsoar_start_rules(task_tag), xcsoar_finish_rules(task_tag))
+    }
+}
+
+/// The start rules of the `<Task>` tag, XCSoar stores the maximum start speed in m/s
+fn xcsoar_start_rules(task_tag: &str) -> StartRules {
+    let number = |name: &str| attribute(task_tag, name).and_then(|value| value.parse::<f32>().ok()).filter(|value| *value > 0.);
+    StartRules {
+        gate_open: attribute(task_tag, "start_open_time").and_then(|time| {
+            let mut parts = time.split(':').map(|part| part.parse::<u8>().ok());
+            let (h, m, s) = (parts.next()??, parts.next()??, parts.next().flatten().unwrap_or(0));
+            Time::from_hms(h, m, s).ok()
+        }),
+        max_altitude: number("start_max_height").map(|height| height as i16),
+        altitude_reference: match attribute(task_tag, "start_max_height_ref").is_some_and(|reference| reference.eq_ignore_ascii_case("AGL")) {
+            true => AltitudeReference::Agl,
+            false => AltitudeReference::Msl,
+        },
+        max_ground_speed: number("start_max_speed").map(|speed| speed * 3.6),
+        pre_start_altitude: None,
+    }
+}
+
+fn xcsoar_finish_rules(task_tag: &str) -> FinishRules {
+    FinishRules {
+        min_altitude: attribute(task_tag, "finish_min_height")
+            .and_then(|value| value.parse::<f32>().ok())
+            .filter(|height| *height > 0.)
+            .map(|height| height as i16),
+        altitude_reference: match attribute(task_tag, "finish_height_ref").is_some_and(|reference| reference.eq_ignore_ascii_case("AGL")) {
+            true => AltitudeReference::Agl,
+            false => AltitudeReference::Msl,
+        },
+    }
+}
+
+/// The SeeYou description params of an XCSoar observation zone
+fn xcsoar_zone(zone: &str) -> String {
+    let number = |name: &str| attribute(zone, name).and_then(|value| value.parse::<f32>().ok());
+    let radius = number("radius").map_or(XCSOAR_SECTOR_RADIUS, |radius| radius as Meters);
+    let inner_radius = number("inner_radius").map_or(0, |radius| radius as Meters);
+    match attribute(zone, "type").as_deref() {
+        Some("Line") => format!("R1={}m,A1=180,R2=0m,A2=0,Line=1", number("length").map_or(radius * 2, |length| length as Meters) / 2),
+        Some("FAISector") | Some("SymmetricQuadrant") => format!("R1={}m,A1=45,R2=0m,A2=0", radius),
+        Some("Keyhole") => format!("R1={}m,A1=45,R2=500m,A2=180", radius),
+        Some("CustomKeyhole") => format!("R1={}m,A1={},R2={}m,A2=180", radius, number("angle").map_or(90, |angle| angle as u16) / 2, inner_radius),
+        Some("BGAFixedCourse") => "R1=20000m,A1=45,R2=500m,A2=180".to_string(),
+        Some("BGAEnhancedOption") => "R1=10000m,A1=90,R2=500m,A2=180".to_string(),
+        Some("BGAStartSector") => "R1=5000m,A1=90,R2=0m,A2=0".to_string(),
+        Some("Sector") | Some("AnnularSector") => {
+            let start = number("start_radial").unwrap_or(0.);
+            let width = (number("end_radial").unwrap_or(0.) - start).rem_euclid(360.);
+            let bisector = (start + width / 2.).rem_euclid(360.);
+            format!("R1={}m,A1={},R2={}m,A2=0,A12={}", radius, (width / 2.) as u16, inner_radius, bisector as u16)
+        }
+        _ => format!("R1={}m,A1=180,R2={}m,A2=0", radius, inner_radius), //cylinders and annuli
+    }
+}
+
+/// The first tag with the given name, e.g. `<Location latitude="56.0" longitude="9.1"/>`
+fn tag<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let regex = Regex::new(format!("<{}[\\s/>][^>]*>?", name).as_str()).expect("regex failed to compile");
+    regex.find(xml).map(|m| m.as_str())
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let regex = Regex::new(format!("\\s{}=\"([^\"]*)\"", name).as_str()).expect("regex failed to compile");
+    regex.captures(tag).map(|captures| captures[1].to_string())
+}
+
+/// Splits a line of a `.cup` file on commas that are not inside quotes and removes the quotes
+fn split_cup_line(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut quoted = false;
+    for c in line.trim().chars() {
+        match c {
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(String::new()),
+            c => fields.last_mut().expect("unreachable").push(c),
+        }
+    }
+    fields
+}
+
+/// Converts a `.cup` coordinate like `5600.500N` or `00906.683E`, the degrees are the first `degree_digits` digits
+fn cup_coordinate(field: &str, degree_digits: usize) -> Option<f32> {
+    let hemisphere = field.chars().last()?;
+    let number = field.get(..field.len() - 1)?;
+    let degrees = number.get(..degree_digits)?.parse::<f32>().ok()?;
+    let minutes = number.get(degree_digits..)?.parse::<f32>().ok()?;
+    let decimal = degrees + minutes / 60.;
+    match hemisphere {
+        'N' | 'E' => Some(decimal),
+        'S' | 'W' => Some(-decimal),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::task::{TaskComponent, TaskType, ZoneOrientation};
+    use super::*;
+
+    const CUP: &str = r#"name,code,country,lat,lon,elev,style,rwdir,rwlen,freq,desc
+"Arnborg","ARN",DK,5600.633N,00900.867E,85.0m,5,,,,
+"Fasterholt","FAS",DK,5600.500N,00906.683E,47.0m,1,,,,
+"Viborg","VIB",DK,5624.583N,00924.583E,5.0m,1,,,,
+-----Related Tasks-----
+"Day 1","Arnborg","Fasterholt","Viborg","Arnborg","Arnborg"
+Options,NoStart=12:57:00,TaskTime=02:00:00,WpDis=False,MaxStartAlt=1500m,StartAltRef=AGL,MaxStartSpeed=150kmh,MinFinishAlt=300m,FinishAltRef=AGL
+ObsZone=0,Style=2,R1=5000m,A1=180,Line=1
+ObsZone=1,Style=1,R1=20000m,A1=180,AAT=1
+ObsZone=2,Style=3,R1=3000m,A1=180
+"#;
+
+    const XCSOAR: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<Task type="RT" aat_min_time="7200" start_max_speed="40" start_max_height="2000" start_max_height_ref="MSL" start_open_time="13:30" finish_min_height="250" finish_height_ref="MSL">
+  <Point type="Start">
+    <Waypoint name="Fasterholt" id="1"><Location latitude="56.008" longitude="9.111"/></Waypoint>
+    <ObservationZone type="Line" length="10000"/>
+  </Point>
+  <Point type="Turn">
+    <Waypoint name="Viborg" id="2"><Location latitude="56.410" longitude="9.410"/></Waypoint>
+    <ObservationZone type="Keyhole"/>
+  </Point>
+  <Point type="Finish">
+    <Waypoint name="Arnborg" id="3"><Location latitude="56.011" longitude="9.014"/></Waypoint>
+    <ObservationZone type="Cylinder" radius="3000"/>
+  </Point>
+</Task>
+"#;
+
+    #[test]
+    fn cup_task_is_read() {
+        let task = Task::from_cup(CUP).expect("failed to read task");
+        assert!(matches!(task.task_type, TaskType::AAT(ref time) if *time == Time::from_hms(2, 0, 0).unwrap()));
+        assert_eq!(task.points.len(), 3);
+        match &task.points[0] {
+            TaskComponent::Start(start) => {
+                assert_eq!(start.name.as_deref(), Some("Fasterholt"));
+                assert!((start.latitude - 56.00833).abs() < 1e-4);
+                assert!(start.line);
+                assert_eq!(start.r1, 5000);
+            }
+            _ => panic!("the first point is not the start"),
+        }
+        assert_eq!(task.points[1].inner().r1, 20000);
+        assert!(matches!(task.points[2], TaskComponent::Finish(_)));
+        assert_eq!(task.start_rules, StartRules {
+            gate_open: Time::from_hms(12, 57, 0).ok(),
+            max_altitude: Some(1500),
+            altitude_reference: AltitudeReference::Agl,
+            max_ground_speed: Some(150.),
+            pre_start_altitude: None,
+        });
+        assert_eq!(task.finish_rules, FinishRules { min_altitude: Some(300), altitude_reference: AltitudeReference::Agl });
+
+        let unknown = CUP.replace("\"Viborg\",\"Arnborg\",\"Arnborg\"", "\"Skive\",\"Arnborg\",\"Arnborg\"");
+        assert!(matches!(Task::from_cup(&unknown), Err(ParseError { kind: ParseErrorKind::UnknownWaypoint(name), .. }) if name == "Skive"));
+    }
+
+    #[test]
+    fn xcsoar_task_is_read() {
+        let task = Task::from_xcsoar(XCSOAR).expect("failed to read task");
+        assert!(matches!(task.task_type, TaskType::AST));
+        assert_eq!(task.points.len(), 3);
+        let start = task.points[0].inner();
+        assert!(start.line);
+        assert_eq!(start.r1, 5000);
+        assert_eq!(start.orientation, ZoneOrientation::ToNext);
+        let keyhole = task.points[1].inner();
+        assert_eq!((keyhole.r1, keyhole.a1, keyhole.r2, keyhole.a2), (10000, 45, 500, 180));
+        assert_eq!(task.points[2].inner().r1, 3000);
+        assert_eq!(task.start_rules.gate_open, Time::from_hms(13, 30, 0).ok());
+        assert_eq!((task.start_rules.max_altitude, task.start_rules.altitude_reference), (Some(2000), AltitudeReference::Msl));
+        assert_eq!(task.start_rules.max_ground_speed, Some(144.));
+        assert_eq!(task.finish_rules, FinishRules { min_altitude: Some(250), altitude_reference: AltitudeReference::Msl });
+        assert!(task.points[2].inner().reduce);
+
+        let aat = XCSOAR.replace("type=\"RT\"", "type=\"AAT\"").replace("type=\"Turn\"", "type=\"Area\"");
+        let task = Task::from_xcsoar(&aat).expect("failed to read task");
+        assert!(matches!(task.task_type, TaskType::AAT(ref time) if *time == Time::from_hms(2, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn task_from_file_matches_the_declaration() {
+        let contents = util::get_contents("examples/ast.igc").expect("failed to read file");
+        let declared = Task::parse(&contents).expect("failed to parse task");
+        assert!(declared.matches(&declared.clone()));
+        assert!(!declared.matches(&Task::from_xcsoar(XCSOAR).expect("failed to read task")));
+    }
+}
@@ -7,13 +7,22 @@ use igc_parser::records::Record;
 use regex::Regex;
 use igc_parser::records::fix::Fix as ParserFix;
 
-#[derive(Clone)]
+pub const SECONDS_PER_DAY: u32 = 86_400;
+
+#[derive(Clone, Default)]
 pub struct Fix {
     pub timestamp: u32,
     pub latitude: f32, //positive is north
     pub longitude: f32, //positive is east
     pub alt: Option<i16>,
-    pub alt_igc: i16,
+    pub alt_igc: i16, //pressure altitude, replaced by the chosen altitude before the analysis
+    pub enl: Option<u16>, //environmental noise level 0-999
+    pub mop: Option<u16>, //means of propulsion sensor 0-999
+    pub ias: Option<f32>, //indicated airspeed in km/h
+    pub tas: Option<f32>, //true airspeed in km/h
+    pub heading: Option<u16>, //true heading in degrees, a magnetic heading is not read
+    pub track: Option<u16>, //ground track in degrees
+    pub vario: Option<f32>, //total energy compensated vertical speed in m/s
 }
 
 impl Fix {
@@ -26,11 +35,24 @@ impl Fix {
             longitude: lon,
             alt: rec.gps_alt,
             alt_igc: rec.pressure_alt,
+            ..Default::default()
         }
     }
+
+    /// Reads the B-record extensions declared in the I-record from the raw B-record line
+    pub fn with_extensions(mut self, line: &str, extensions: &FixExtensions) -> Self {
+        self.enl = extensions.get(line, "ENL");
+        self.mop = extensions.get(line, "MOP");
+        self.ias = extensions.get_decimal(line, "IAS", 3, false);
+        self.tas = extensions.get_decimal(line, "TAS", 3, false);
+        self.heading = extensions.get(line, "HDT"); //the wind is fitted without a heading rather than with a magnetic one
+        self.track = extensions.get(line, "TRT").or_else(|| extensions.get(line, "TRM"));
+        self.vario = extensions.get_decimal(line, "VAT", 2, true);
+        self
+    }
     pub fn to_string(&self) -> String {
         format!("Fix{{time: {}:{}:{}, lat: {}, lon: {}, alt: {}}}",
-                self.timestamp / 3600,
+                self.timestamp % SECONDS_PER_DAY / 3600,
                 self.timestamp % 3600 / 60,
                 self.timestamp % 60,
                 self.latitude,
@@ -43,6 +65,45 @@ impl Fix {
     }
 }
 
+/// The positions of the B-record extensions, as declared by the I-record
+pub struct FixExtensions {
+    positions: Vec<(String, usize, usize)>,
+}
+
+impl FixExtensions {
+    pub fn parse(contents: &str) -> Self {
+        let i_record = contents.lines().find(|line| line.starts_with('I')).unwrap_or("I00");
+        let declarations = i_record.get(3..).unwrap_or("");
+        let positions = declarations.as_bytes().chunks(7)
+            .filter_map(|declaration| {
+                let declaration = std::str::from_utf8(declaration).ok()?;
+                let start = declaration.get(0..2)?.parse::<usize>().ok()?;
+                let end = declaration.get(2..4)?.parse::<usize>().ok()?;
+                let code = declaration.get(4..7)?.to_string();
+                if start == 0 || end < start { return None };
+                Some((code, start - 1, end)) //the positions are 1-indexed and inclusive
+            })
+            .collect();
+        Self { positions }
+    }
+
+    pub fn get<T: FromStr>(&self, line: &str, code: &str) -> Option<T> {
+        let (_, start, end) = self.positions.iter().find(|(c, _, _)| c == code)?;
+        line.get(*start..*end)?.trim().parse().ok()
+    }
+
+    /// Reads an extension where the digits after the first `integer_digits` are decimals,
+    /// loggers declare wider fields than the standard to log e.g. TAS as 11874 for 118.74 km/h.
+    /// The first character of a `signed` field is the sign, so it is not counted as a digit
+    pub fn get_decimal(&self, line: &str, code: &str, integer_digits: usize, signed: bool) -> Option<f32> {
+        let (_, start, end) = self.positions.iter().find(|(c, _, _)| c == code)?;
+        let value = line.get(*start..*end)?.trim().parse::<i32>().ok()? as f32;
+        let digits = (end - start).saturating_sub(signed as usize);
+        let decimals = digits.saturating_sub(integer_digits) as i32;
+        Some(value / 10f32.powi(decimals))
+    }
+}
+
 pub struct TurnpointRecord {
     pub latitude: f32,
     pub longitude: f32,
@@ -91,14 +152,35 @@ fn map_parsed_contents<F,T>(contents: &str, f: F) -> T
     f(&records)
 }
 
+/// The fixes of the log, timed in seconds since midnight UTC of the day of the first fix.
+/// B records only hold the time of day, so each fix is placed in the day closest to the fix before it,
+/// and fixes after the UTC day rolls over are counted past 86400 to keep the time increasing
+/// through flights in e.g. Australia or the Americas
 pub fn get_fixes(contents: &str) -> Vec<Fix> {
-    let f = |records: &Vec<Record>| records.iter().filter_map( |record|
-        match record {
-            Record::B(brecord) => Some(Fix::from(brecord)),
+    let extensions = FixExtensions::parse(contents);
+    let mut previous: Option<u32> = None;
+    contents.lines().filter(|line| line.starts_with('B')).filter_map(|line|
+        match Record::parse(line) {
+            Ok(Record::B(brecord)) => {
+                let mut fix = Fix::from(&brecord).with_extensions(line, &extensions);
+                if let Some(previous) = previous { fix.timestamp = unwrap_day(fix.timestamp, previous) };
+                previous = Some(fix.timestamp);
+                Some(fix)
+            },
             _ => None,
         }
-    ).collect::<Vec<Fix>>();
-    map_parsed_contents(contents, f)
+    ).collect::<Vec<Fix>>()
+}
+
+/// Places a time of day in the day that is closest to the reference time,
+/// so that 00:10 after a reference at 23:50 becomes 24:10
+pub fn unwrap_day(seconds: u32, reference: u32) -> u32 {
+    let seconds = seconds % SECONDS_PER_DAY;
+    let day = reference / SECONDS_PER_DAY;
+    [day.checked_sub(1), Some(day), Some(day + 1)].into_iter().flatten()
+        .map(|day| seconds + day * SECONDS_PER_DAY)
+        .min_by_key(|candidate| candidate.abs_diff(reference))
+        .expect("unreachable")
 }
 
 fn get_l_records_strings(contents: String) -> Vec<String> {
@@ -135,7 +217,32 @@ pub fn get_turnpoint_locations(contents: &str) -> Vec<TurnpointRecord> {
     get_turnpoints_from_l_records(c_record_candidate)
 }
 
+/// The points of a standard C record declaration, without the takeoff and landing
+pub fn get_declared_turnpoints(contents: &str) -> Vec<TurnpointRecord> {
+    let c_records = contents.lines().filter(|line| line.starts_with('C')).collect::<Vec<&str>>();
+    let turnpoint_count = c_records.first() //the first record is the header ending with the number of turnpoints
+        .and_then(|header| header.get(23..25))
+        .and_then(|count| count.parse::<usize>().ok());
+    let points = c_records.iter()
+        .filter_map(|line| match Record::parse(line) {
+            Ok(Record::C(TaskInfo::TaskPoint(c))) => Some(TurnpointRecord::from_c_record_tp(&c)),
+            _ => None,
+        })
+        .collect::<Vec<TurnpointRecord>>();
+    match turnpoint_count {
+        Some(count) if points.len() == count + 4 => points.into_iter().skip(1).take(count + 2).collect(),
+        _ => points.into_iter()
+            .filter(|tp| !(tp.longitude == 0. && tp.latitude == 0. && tp.name.is_none())) //removes the empty takeoff and landing
+            .collect(),
+    }
+}
+
 pub fn get_task_time(contents: &str) -> Option<Time> {
+    get_task_options(contents).and_then(|s| parse_task_time(&s))
+}
+
+/// The `LSEEYOU TSK` line of the log with the options of the task
+pub fn get_task_options(contents: &str) -> Option<String> {
     let f = |records: &Vec<Record>| records.iter().filter_map( |record|
         match record {
             Record::L(lrecord) =>
@@ -148,23 +255,20 @@ pub fn get_task_time(contents: &str) -> Option<Time> {
         }).collect::<Vec<String>>();
     let task_string = map_parsed_contents(contents, f);
 
-    match task_string.first() {
-        Some(s) => {
-            let regex = Regex::new("TaskTime=[0-9][0-9]:[0-9][0-9]:[0-9][0-9]").ok()?;
-            let matc = regex.find(s);
-
-            match matc {
-                None => None,
-                Some(matc) => {
-                    let time_string = &s[matc.start()+"TaskTime=".len() .. matc.end()].to_string();
-                    Some(
-                        Time::parse(&time_string.replacen(':', "", 3)).ok()?
-                    )
-                },
-            }
-        },
-        None => None,
-    }
+    task_string.into_iter().next()
+}
+
+/// Reads the `TaskTime=HH:MM:SS` param that SeeYou writes in task options
+pub(crate) fn parse_task_time(s: &str) -> Option<Time> {
+    parse_option_time(s, "TaskTime")
+}
+
+/// Reads a `key=HH:MM:SS` param of the task options
+pub(crate) fn parse_option_time(s: &str, key: &str) -> Option<Time> {
+    let regex = Regex::new(&format!("{}=[0-9][0-9]:[0-9][0-9]:[0-9][0-9]", key)).ok()?;
+    let matc = regex.find(s)?;
+    let time_string = &s[matc.start() + key.len() + 1 .. matc.end()].to_string();
+    Time::parse(&time_string.replacen(':', "", 3)).ok()
 }
 
 pub fn get_turnpoint_descriptions(contents: &str) -> Vec<String> {
@@ -226,6 +330,65 @@ mod tests {
         };
     }
 
+    #[test]
+    fn fix_extensions_should_be_parsed() {
+        let extensions = FixExtensions::parse("I103638FXA3941ENL4246TAS4751GSP5254TRT5559VAT6063OAT6466MOP6769CUR7073ACZ");
+        let line = "B0914025439402N02403298EA00023000870060040000000018000-000102930039900100";
+        let fix = Fix::from(&match Record::parse(line) {
+            Ok(Record::B(brecord)) => brecord,
+            _ => panic!("failed to parse B-record"),
+        }).with_extensions(line, &extensions);
+        assert_eq!(fix.enl, Some(4));
+        assert_eq!(fix.mop, Some(3));
+        assert_eq!(fix.tas, Some(0.));
+        assert_eq!(fix.track, Some(0));
+        assert_eq!(fix.vario, Some(-0.01));
+        assert_eq!((fix.ias, fix.heading), (None, None));
+
+        let extensions = FixExtensions::parse("I083638FXA3941ENL4246TAS4751GSP5254TRT5559VAT6063OAT6467ACZ");
+        let line = "B1208385608123N00940122EA0118501265006004118741139831100010014001200000000";
+        let fix = Fix::from(&match Record::parse(line) {
+            Ok(Record::B(brecord)) => brecord,
+            _ => panic!("failed to parse B-record"),
+        }).with_extensions(line, &extensions);
+        assert_eq!(fix.tas, Some(118.74));
+        assert_eq!(fix.track, Some(311));
+        assert_eq!(fix.vario, Some(0.1));
+
+        let extensions = FixExtensions::parse("I023638FXA3941HDM");
+        let line = "B1208385608123N00940122EA0118501265006270";
+        let fix = Fix::from(&match Record::parse(line) {
+            Ok(Record::B(brecord)) => brecord,
+            _ => panic!("failed to parse B-record"),
+        }).with_extensions(line, &extensions);
+        assert_eq!(fix.heading, None); //only a true heading is used
+    }
+
+    #[test]
+    fn negative_vario_is_scaled_by_the_field_width() {
+        let extensions = FixExtensions::parse("I083638FXA3941ENL4246TAS4751GSP5254TRT5559VAT6063OAT6467ACZ");
+        let vario = |field: &str| {
+            let mut line = "B1208385608123N00940122EA0118501265006004118741139831100010014001200000000".to_string();
+            line.replace_range(54..59, field);
+            extensions.get_decimal(&line, "VAT", 2, true)
+        };
+        assert_eq!(vario("-0001"), Some(-0.01));
+        assert_eq!(vario("-0250"), Some(-2.5));
+        assert_eq!(vario("  -12"), Some(-0.12)); //padded with spaces instead of zeros
+        assert_eq!(vario("00250"), Some(2.5));
+    }
+
+    #[test]
+    fn fixes_continue_past_midnight() {
+        assert_eq!(unwrap_day(600, 86_000), 87_000);
+        assert_eq!(unwrap_day(86_000, 87_000), 86_000);
+        assert_eq!(unwrap_day(40_000, 40_100), 40_000);
+
+        let contents = "HFDTE010123\nB2359585600708N00901426EA0038000800\nB0000025600708N00901426EA0038000800\nB0000065600708N00901426EA0038000800";
+        let timestamps = get_fixes(contents).iter().map(|fix| fix.timestamp).collect::<Vec<u32>>();
+        assert_eq!(timestamps, vec![86_398, 86_402, 86_406]);
+    }
+
     #[test]
     fn getting_time() {
         if let Some(time) = get_task_time("LSEEYOU TSK,NoStart=12:57:00,TaskTime=02:00:00,WpDis=False") {
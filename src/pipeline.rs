@@ -0,0 +1,401 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use igc_parser::records::util::{Date, Time};
+use thiserror::Error;
+use crate::analysis::altitude::{AltitudeCorrection, AltitudeSource};
+use crate::analysis::calculation::Calculation;
+use crate::analysis::segmenting::{Flight, SegmentationConfig};
+use crate::analysis::util::Offsetable;
+use crate::excel::file_writer;
+use crate::excel::file_writer::ExcelError;
+use crate::parser::pilot_info::PilotInfo;
+use crate::parser::security::Verification;
+use crate::parser::error;
+use crate::parser::error::ParseError;
+use crate::parser::task::{DefaultZones, Task};
+use crate::parser::util;
+use crate::web_handling::soaringspot;
+use crate::web_handling::soaringspot::SoaringSpot;
+
+type Kph = f32;
+type FloatMeters = f32;
+
+/// One pilot's log and what the official scoring reported for it, if anything
+#[derive(Clone)]
+pub struct PilotInput {
+    pub name: String, //identifies the log when it fails
+    pub contents: String,
+    pub start_time: Option<Time>, //local time
+    pub speed: Option<Kph>,
+    pub distance: Option<FloatMeters>,
+}
+
+impl PilotInput {
+    pub fn new(name: String, contents: String) -> Self {
+        Self { name, contents, start_time: None, speed: None, distance: None }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AnalysisError {
+    #[error("no usable task declaration: {0}")]
+    Task(ParseError),
+    #[error("no valid fixes")]
+    NoFixes,
+    #[error("missing pilot information: {0}")]
+    PilotInfo(ParseError),
+    #[error("the flight could not be split into legs")]
+    Calculation,
+    #[error("the declared task differs from the task of the day")]
+    TaskMismatch,
+}
+
+/// A log that could not be analysed and why, with every problem found when parsing it
+#[derive(Debug)]
+pub struct Failure {
+    pub name: String,
+    pub error: AnalysisError,
+    pub diagnostics: Vec<ParseError>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+}
+
+/// Where the task of each pilot comes from
+#[derive(Clone)]
+pub enum TaskSource {
+    /// The task declared in each log, plain declarations are given the default zones
+    Declared(DefaultZones),
+    /// The task of the day replaces the declarations
+    Override(Task),
+    /// The task of the day is used, logs that declare a different task fail
+    Validate(Task),
+}
+
+impl Default for TaskSource {
+    fn default() -> Self {
+        Self::Declared(DefaultZones::default())
+    }
+}
+
+/// All the logs of one competition day analysed with the same segmentation
+pub struct CompetitionDay {
+    pub inputs: Vec<PilotInput>,
+    pub config: SegmentationConfig,
+    pub task: TaskSource,
+    pub altitude: AltitudeSource,
+}
+
+pub struct DayResult {
+    pub date: Option<Date>,
+    pub calculations: Vec<Calculation>,
+    pub failures: Vec<Failure>,
+}
+
+impl CompetitionDay {
+    pub fn new(inputs: Vec<PilotInput>, config: SegmentationConfig) -> Self {
+        Self { inputs, config, task: TaskSource::default(), altitude: AltitudeSource::default() }
+    }
+
+    pub fn with_task(mut self, task: TaskSource) -> Self {
+        self.task = task;
+        self
+    }
+
+    pub fn with_altitude(mut self, altitude: AltitudeSource) -> Self {
+        self.altitude = altitude;
+        self
+    }
+
+    /// Downloads the logs of a SoaringSpot results page into `dir` together with the reported start times, speeds and distances
+    pub async fn from_soaringspot<F: FnMut(Progress)>(url: &str, dir: &str, config: SegmentationConfig, mut on_progress: F) -> Result<Self, String> {
+        let spot = SoaringSpot::new(url.to_string()).await?;
+        fs::create_dir_all(dir).map_err(|_| format!("Unable to create {}", dir))?;
+        let _ = soaringspot::delete_files_in_dir(dir);
+        let dir = dir.to_string();
+
+        let links = spot.get_download_links();
+        let total = links.len();
+        let mut downloaded = vec![];
+        for (index, link) in links.iter().enumerate() {
+            downloaded.push(match link {
+                Some(link) => soaringspot::download(link, &dir, index).await.is_ok(),
+                None => false,
+            });
+            on_progress(Progress { done: index + 1, total });
+        }
+
+        let inputs = spot.get_start_times().into_iter()
+            .zip(spot.get_speeds())
+            .zip(spot.get_distances())
+            .zip(downloaded)
+            .enumerate()
+            .filter_map(|(index, (((start_time, speed), distance), downloaded))| {
+                if !downloaded { return None };
+                let name = format!("{:0>3}.igc", index + 1);
+                let contents = util::get_contents(format!("{}{}", dir, name).as_str()).ok()?;
+                Some(PilotInput { name, contents, start_time, speed, distance })
+            })
+            .collect();
+        let _ = soaringspot::delete_files_in_dir(&dir);
+        Ok(Self::new(inputs, config))
+    }
+
+    /// Reads every IGC file in the folder, the task and start of each pilot comes from their own log
+    pub fn from_folder(folder: &str, config: SegmentationConfig) -> Result<Self, String> {
+        let mut paths = fs::read_dir(folder).map_err(|_| format!("Unable to read {}", folder))?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("igc")))
+            .collect::<Vec<PathBuf>>();
+        paths.sort();
+        Self::from_files(&paths, config)
+    }
+
+    pub fn from_files<P: AsRef<Path>>(paths: &[P], config: SegmentationConfig) -> Result<Self, String> {
+        let inputs = paths.iter().map(|path| {
+            let path = path.as_ref();
+            let contents = util::get_contents(path.display().to_string().as_str())
+                .map_err(|_| format!("Unable to read {}", path.display()))?;
+            let name = path.file_name().map_or(path.display().to_string(), |name| name.to_string_lossy().to_string());
+            Ok(PilotInput::new(name, contents))
+        }).collect::<Result<Vec<PilotInput>, String>>()?;
+        Ok(Self::new(inputs, config))
+    }
+
+    pub fn date(&self) -> Option<Date> {
+        self.inputs.iter().find_map(|input| util::get_date(&input.contents).ok())
+    }
+
+    pub fn run<F: FnMut(Progress)>(&self, mut on_progress: F) -> DayResult {
+        let mut result = DayResult::new(self.date());
+        let total = self.inputs.len();
+        for (index, input) in self.inputs.iter().enumerate() {
+            result.push(input, &self.task, analyze(input, &self.config, &self.task, self.altitude));
+            on_progress(Progress { done: index + 1, total });
+        }
+        result
+    }
+}
+
+/// Parses the task, segments the flight and makes the calculation for a single log
+pub fn analyze(input: &PilotInput, config: &SegmentationConfig, task: &TaskSource, altitude: AltitudeSource) -> Result<Calculation, AnalysisError> {
+    let task = match task {
+        TaskSource::Declared(zones) => Task::parse_with(&input.contents, zones).map_err(AnalysisError::Task)?,
+        TaskSource::Override(task) => task.clone(),
+        TaskSource::Validate(task) => match Task::parse(&input.contents) {
+            Ok(declared) if !declared.matches(task) => return Err(AnalysisError::TaskMismatch),
+            _ => task.clone(), //logs without a declaration are given the task of the day
+        },
+    };
+    let mut fixes = util::get_fixes(&input.contents);
+    let altitude = AltitudeCorrection::new(&fixes, altitude);
+    altitude.apply(&mut fixes);
+    let flight = Flight::make(fixes, config).ok_or(AnalysisError::NoFixes)?;
+    let pilot_info = PilotInfo::parse(&input.contents).map_err(AnalysisError::PilotInfo)?;
+    let first_fix = flight.fixes.first().map_or(0, |fix| fix.timestamp);
+    let start_time = input.start_time.clone().map(|mut time| {
+        time.offset(-pilot_info.time_zone);
+        util::unwrap_day(time.seconds_since_midnight(), first_fix)
+    });
+    let mut calculation = Calculation::new(task, flight, pilot_info, start_time, input.speed, input.distance).ok_or(AnalysisError::Calculation)?;
+    calculation.security = Some(Verification::verify(&input.contents));
+    calculation.altitude = altitude;
+    Ok(calculation)
+}
+
+impl DayResult {
+    pub fn new(date: Option<Date>) -> Self {
+        Self { date, calculations: vec![], failures: vec![] }
+    }
+
+    pub fn push(&mut self, input: &PilotInput, task: &TaskSource, result: Result<Calculation, AnalysisError>) {
+        match result {
+            Ok(calculation) => self.calculations.push(calculation),
+            Err(error) => {
+                let zones = match task {
+                    TaskSource::Declared(zones) => zones.clone(),
+                    _ => DefaultZones::default(),
+                };
+                let diagnostics = error::diagnostics(&input.contents, &zones);
+                self.failures.push(Failure { name: input.name.clone(), error, diagnostics })
+            }
+        }
+    }
+
+    pub fn write_excel(&self, path: &str) -> Result<(), ExcelError> {
+        let task = self.calculations.first().ok_or(ExcelError::Excel)?.get_task();
+        let date = self.date.clone().unwrap_or(Date { d: 0, m: 0, y: 0 });
+        file_writer::make_excel_file(path, task, &self.calculations, date)
+    }
+
+    pub fn write_csv(&self, path: &str) -> Result<(), ExcelError> {
+        let task = self.calculations.first().ok_or(ExcelError::Excel)?.get_task();
+        file_writer::make_csv_file(path, task, &self.calculations)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analysis::calculation::{Outcome, TaskPiece};
+    use crate::parser::error::ParseErrorKind;
+    use super::*;
+
+    #[test]
+    fn failures_are_reported_with_their_reason() {
+        let inputs = vec![
+            PilotInput::new("ast.igc".to_string(), util::get_contents("examples/ast.igc").expect("failed to read file")),
+            PilotInput::new("empty.igc".to_string(), "".to_string()),
+        ];
+        let day = CompetitionDay::new(inputs, SegmentationConfig::default());
+        let mut progress = vec![];
+        let result = day.run(|p| progress.push(p));
+        assert_eq!(progress.last(), Some(&Progress { done: 2, total: 2 }));
+        assert_eq!(result.calculations.len(), 1);
+        assert_eq!(result.failures.len(), 1);
+        assert_eq!(result.failures[0].name, "empty.igc");
+        assert!(matches!(result.failures[0].error, AnalysisError::Task(_)));
+        assert!(!result.failures[0].diagnostics.is_empty());
+    }
+
+    #[test]
+    fn start_in_the_middle_of_the_task_is_an_error() {
+        let ast = util::get_contents("examples/ast.igc").expect("failed to read file");
+        let broken = ast.replace("LSEEYOU OZ=4,Style=1,", "LSEEYOU OZ=-1,Style=2,"); //the point before the finish
+        let input = PilotInput::new("broken.igc".to_string(), broken);
+        match analyze(&input, &SegmentationConfig::default(), &TaskSource::default(), AltitudeSource::default()) {
+            Err(AnalysisError::Task(error)) => assert_eq!(error.kind, ParseErrorKind::NoTurnpoints),
+            _ => panic!("the task was accepted"),
+        }
+    }
+
+    #[test]
+    fn folder_is_analysed_without_scoring_data() {
+        let day = CompetitionDay::from_folder("examples", SegmentationConfig::default()).expect("failed to read folder");
+        assert_eq!(day.inputs.len(), 4);
+        let result = day.run(|_| {});
+        assert!(result.failures.is_empty());
+        assert!(result.calculations.iter().all(|calc| calc.security.is_some()));
+        let finished = result.calculations.iter()
+            .filter(|calc| matches!(calc.outcome(), Outcome::Finished { .. }))
+            .collect::<Vec<&Calculation>>();
+        assert!(!finished.is_empty());
+        for calc in finished {
+            assert!(calc.speed(TaskPiece::EntireTask).is_some());
+            assert!(calc.distance(TaskPiece::EntireTask).is_some());
+        }
+    }
+
+    #[test]
+    fn task_of_the_day_overrides_or_validates_declarations() {
+        let ast = util::get_contents("examples/ast.igc").expect("failed to read file");
+        let aat = util::get_contents("examples/aat.igc").expect("failed to read file");
+        let day_task = Task::parse(&ast).expect("failed to parse task");
+        let inputs = vec![
+            PilotInput::new("ast.igc".to_string(), ast.clone()),
+            PilotInput::new("aat.igc".to_string(), aat),
+            PilotInput::new("undeclared.igc".to_string(), ast.lines().filter(|line| !line.starts_with("LCU::C")).collect::<Vec<&str>>().join("\n")),
+        ];
+
+        let day = CompetitionDay::new(inputs.clone(), SegmentationConfig::default()).with_task(TaskSource::Validate(day_task.clone()));
+        let result = day.run(|_| {});
+        assert_eq!(result.calculations.len(), 2);
+        assert_eq!(result.failures.len(), 1);
+        assert_eq!(result.failures[0].name, "aat.igc");
+        assert!(matches!(result.failures[0].error, AnalysisError::TaskMismatch));
+
+        let day = CompetitionDay::new(inputs, SegmentationConfig::default()).with_task(TaskSource::Override(day_task));
+        let result = day.run(|_| {});
+        assert_eq!(result.calculations.len(), 3);
+    }
+
+    #[test]
+    fn flight_crossing_midnight_is_analysed_in_full() {
+        let ast = util::get_contents("examples/ast.igc").expect("failed to read file");
+        let later = ast.lines().map(|line| match line.starts_with('B') {
+            true => {
+                let field = |range: std::ops::Range<usize>| line[range].parse::<u32>().expect("invalid time");
+                let seconds = field(1..3) * 3600 + field(3..5) * 60 + field(5..7) + 13 * 3600;
+                format!("B{:0>2}{:0>2}{:0>2}{}", seconds / 3600 % 24, seconds / 60 % 60, seconds % 60, &line[7..])
+            },
+            false => line.to_string(),
+        }).collect::<Vec<String>>().join("\n");
+        let analyze = |contents: &str| analyze(&PilotInput::new("ast.igc".to_string(), contents.to_string()), &SegmentationConfig::default(), &TaskSource::default(), AltitudeSource::default()).expect("failed to analyze");
+        let (original, later) = (analyze(&ast), analyze(&later));
+
+        assert_eq!(original.total_flight.fixes.len(), later.total_flight.fixes.len());
+        assert_eq!(original.speed(TaskPiece::EntireTask), later.speed(TaskPiece::EntireTask));
+        assert_eq!(original.distance(TaskPiece::EntireTask), later.distance(TaskPiece::EntireTask));
+        let mut finish = original.finish_time(TaskPiece::EntireTask).expect("no finish time");
+        finish.offset(13);
+        assert_eq!(later.finish_time(TaskPiece::EntireTask), Some(finish));
+    }
+
+    #[test]
+    fn altitudes_follow_the_chosen_source() {
+        let inputs = vec![PilotInput::new("ast.igc".to_string(), util::get_contents("examples/ast.igc").expect("failed to read file"))];
+        let day = CompetitionDay::new(inputs, SegmentationConfig::default());
+        let pressure = day.run(|_| {}).calculations.remove(0);
+        let qnh = day.with_altitude(AltitudeSource::Qnh).run(|_| {}).calculations.remove(0);
+        assert_eq!(pressure.altitude, AltitudeCorrection::default());
+        assert_eq!(qnh.altitude.source, AltitudeSource::Qnh);
+        assert_ne!(qnh.altitude.offset, 0);
+        let start_alt = |calc: &Calculation| calc.start_alt(TaskPiece::EntireTask).expect("no start altitude");
+        assert_eq!(start_alt(&qnh), start_alt(&pressure) + qnh.altitude.offset);
+    }
+
+    #[test]
+    fn legs_are_split_where_the_zones_were_crossed() {
+        let input = PilotInput::new("ast.igc".to_string(), util::get_contents("examples/ast.igc").expect("failed to read file"));
+        let calc = analyze(&input, &SegmentationConfig::default(), &TaskSource::default(), AltitudeSource::default()).expect("failed to analyze");
+        let seconds = |time: Option<Time>| time.expect("no time").seconds_since_midnight();
+
+        let mut interpolated = 0;
+        for (i, leg) in calc.legs.iter().enumerate() {
+            let leg = leg.as_ref().expect("leg not flown");
+            let (first, last) = (leg.fixes.first().expect("no fixes"), leg.fixes.last().expect("no fixes"));
+            let (start, end) = (seconds(calc.start_time(TaskPiece::Leg(i))), seconds(calc.finish_time(TaskPiece::Leg(i))));
+            assert!(start <= first.timestamp && end >= last.timestamp); //the leg ends at the first fix of the next leg
+            if start < first.timestamp { interpolated += 1 };
+            if i + 1 < calc.legs.len() { assert_eq!(end, seconds(calc.start_time(TaskPiece::Leg(i + 1)))) };
+        }
+        assert!(interpolated > 0, "no zone was crossed between two fixes");
+        assert_eq!(calc.start_time(TaskPiece::EntireTask), calc.start_time(TaskPiece::Leg(0)));
+        assert_eq!(calc.finish_crossing_time().map(|time| time.round() as u32), Some(seconds(calc.finish_time(TaskPiece::EntireTask))));
+    }
+
+    #[test]
+    fn finish_below_the_minimum_altitude_is_reported() {
+        let ast = util::get_contents("examples/ast.igc").expect("failed to read file");
+        let low = ast.replace("LSEEYOU TSK,", "LSEEYOU TSK,MinFinishAlt=5000m,");
+        let analyze = |contents: &str| analyze(&PilotInput::new("ast.igc".to_string(), contents.to_string()), &SegmentationConfig::default(), &TaskSource::default(), AltitudeSource::default()).expect("failed to analyze");
+        let (original, low) = (analyze(&ast), analyze(&low));
+
+        let finish = match original.outcome() {
+            Outcome::Finished { time } => *time,
+            _ => panic!("the task was not finished"),
+        };
+        let crossing = original.finish_crossing_time().expect("no finish crossing");
+        assert!(crossing <= finish as f32 && crossing > finish as f32 - 10., "crossed at {} and reached at {}", crossing, finish);
+        assert_eq!(original.finish_below_minimum(), None);
+
+        let finish_alt = low.finish_alt(TaskPiece::EntireTask).expect("no finish altitude");
+        let deficit = low.finish_below_minimum().expect("the finish was not below the minimum");
+        assert!((deficit - (5000 - finish_alt)).abs() < 50, "{} m below with a finish at {} m", deficit, finish_alt);
+    }
+
+    #[test]
+    fn area_legs_are_split_at_the_credited_fixes() {
+        let input = PilotInput::new("aat.igc".to_string(), util::get_contents("examples/aat.igc").expect("failed to read file"));
+        let calc = analyze(&input, &SegmentationConfig::default(), &TaskSource::default(), AltitudeSource::default()).expect("failed to analyze");
+        let credited = calc.credited_points();
+        assert_eq!(credited.len(), calc.task.points.len());
+        for (leg, point) in calc.legs.iter().skip(1).zip(&credited[1..credited.len() - 1]) {
+            let first = leg.as_ref().and_then(|leg| leg.fixes.first()).expect("leg not flown");
+            assert_eq!((first.latitude, first.longitude), *point);
+        }
+    }
+}